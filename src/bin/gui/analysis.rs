@@ -0,0 +1,166 @@
+use chess::engine::{
+    chess_move::ChessMove,
+    piece::PieceColor,
+    position::Position,
+    san::to_san,
+    search::{PvLine, SearchLimits, SearchResult, Searcher, StopSignal},
+};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Lines shown while analysing.
+const ANALYSIS_LINES: usize = 3;
+
+/// Latest completed depth of the analysis of `position`.
+#[derive(Clone)]
+pub struct AnalysisSnapshot {
+    pub position: Position,
+    pub depth: u8,
+    pub nodes: u64,
+    pub lines: Vec<PvLine>,
+}
+
+impl AnalysisSnapshot {
+    pub fn best_move(&self) -> Option<ChessMove> {
+        self.lines.first().and_then(|line| line.pv.first().copied())
+    }
+
+    /// Score of the best line from White's point of view.
+    pub fn white_score(&self) -> Option<i32> {
+        let score = self.lines.first()?.score;
+        Some(match self.position.side_to_move() {
+            PieceColor::White => score,
+            PieceColor::Black => -score,
+        })
+    }
+}
+
+// Hash of the analysed position and the ply it was reached at, so the same
+// position later in the game is analysed again with its own history.
+type AnalysisKey = (u64, usize);
+
+struct Running {
+    key: AnalysisKey,
+    stop: StopSignal,
+}
+
+/// Engine analysing the displayed position without a time limit on a
+/// background thread, until the position changes or analysis is turned off.
+pub struct Analyzer {
+    searcher: Arc<Mutex<Searcher>>,
+    running: Option<Running>,
+    latest: Arc<Mutex<Option<(AnalysisKey, AnalysisSnapshot)>>>,
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        let mut searcher = Searcher::default();
+        searcher.set_multi_pv(ANALYSIS_LINES);
+        Self {
+            searcher: Arc::new(Mutex::new(searcher)),
+            running: None,
+            latest: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Makes sure `position`, reached after `history`, is being analysed,
+    /// restarting the search if it was busy with another one.
+    pub fn analyze(&mut self, position: &Position, history: Vec<u64>, ctx: &egui::Context) {
+        let key = (position.hash(), history.len());
+        if self
+            .running
+            .as_ref()
+            .is_some_and(|running| running.key == key)
+        {
+            return;
+        }
+        self.stop();
+        // Made before the thread starts so a stop can never be missed, even
+        // while an earlier search still holds the searcher.
+        let limits = SearchLimits::default();
+        let stop = limits.stop.clone();
+        let searcher = Arc::clone(&self.searcher);
+        let latest = Arc::clone(&self.latest);
+        let position = *position;
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let searcher = searcher.lock().unwrap();
+            let mut publish = |result: &SearchResult| {
+                if limits.stop.is_stopped() {
+                    return;
+                }
+                let snapshot = AnalysisSnapshot {
+                    position,
+                    depth: result.depth,
+                    nodes: result.nodes,
+                    lines: result.lines.clone(),
+                };
+                *latest.lock().unwrap() = Some((key, snapshot));
+                ctx.request_repaint();
+            };
+            searcher.search_with_progress(&position, &history, &limits, &mut publish);
+        });
+        self.running = Some(Running { key, stop });
+    }
+
+    /// Analysis of the position currently being searched, once the first
+    /// depth is done.
+    pub fn snapshot(&self) -> Option<AnalysisSnapshot> {
+        let running = self.running.as_ref()?;
+        let latest = self.latest.lock().unwrap();
+        latest
+            .as_ref()
+            .filter(|(key, _)| *key == running.key)
+            .map(|(_, snapshot)| snapshot.clone())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.stop.stop();
+        }
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// `+1.25` in pawns, or `#3` / `#-3` for a mate.
+pub fn format_score(score: i32) -> String {
+    match SearchResult::mate_in(score) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:+.2}", score as f64 / 100.0),
+    }
+}
+
+/// `pv` in SAN with move numbers, starting from `position`.
+pub fn san_line(position: &Position, pv: &[ChessMove]) -> String {
+    let mut position = *position;
+    let mut tokens = Vec::new();
+    for (index, mv) in pv.iter().enumerate() {
+        if !position.is_legal(mv) {
+            break;
+        }
+        match position.side_to_move() {
+            PieceColor::White => tokens.push(format!("{}.", position.fullmove_number())),
+            PieceColor::Black if index == 0 => {
+                tokens.push(format!("{}...", position.fullmove_number()))
+            }
+            PieceColor::Black => {}
+        }
+        tokens.push(to_san(&position, mv));
+        position = position.play(mv);
+    }
+    tokens.join(" ")
+}
+
+/// Share of the bar filled for White: a logistic curve of the score that
+/// is nearly full at a few pawns and full at a forced mate.
+pub fn white_share(white_score: i32) -> f32 {
+    if SearchResult::mate_in(white_score).is_some() {
+        return if white_score > 0 { 1.0 } else { 0.0 };
+    }
+    1.0 / (1.0 + 10f32.powf(-white_score as f32 / 400.0))
+}
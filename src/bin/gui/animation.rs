@@ -0,0 +1,107 @@
+use crate::geometry::BoardGeometry;
+use crate::theme::PieceRenderer;
+use chess::engine::{
+    chess_move::{ChessMove, Square},
+    piece::Piece,
+    position::Position,
+};
+
+struct Slide {
+    piece: Piece,
+    from: Square,
+    to: Square,
+}
+
+/// A captured piece disappearing, or coming back when the capture is taken
+/// back.
+struct Fade {
+    piece: Piece,
+    square: Square,
+    appearing: bool,
+}
+
+/// Pieces moving between two positions one move apart, drawn over the
+/// later of the two.
+pub struct MoveAnimation {
+    slides: Vec<Slide>,
+    fade: Option<Fade>,
+}
+
+impl MoveAnimation {
+    /// Animates `mv` played from `before`, or taking it back if `reverse`.
+    pub fn new(before: &Position, mv: ChessMove, reverse: bool) -> Self {
+        let after = before.play(&mv);
+        let mut slides = Vec::new();
+        // A promoting pawn slides as the piece it becomes.
+        let (mover, arriving) = (before.piece_at(mv.from), after.piece_at(mv.to));
+        if let Some(piece) = if reverse { mover } else { arriving } {
+            slides.push(Slide {
+                piece,
+                from: mv.from,
+                to: mv.to,
+            });
+        }
+        if let Some((rook_from, rook_to)) = before.castling_rook_squares(&mv)
+            && let Some(rook) = before.piece_at(rook_from)
+        {
+            slides.push(Slide {
+                piece: rook,
+                from: rook_from,
+                to: rook_to,
+            });
+        }
+        let captured = before
+            .captured_square(&mv)
+            .and_then(|square| before.piece_at(square).map(|piece| (piece, square)));
+        if reverse {
+            for slide in &mut slides {
+                std::mem::swap(&mut slide.from, &mut slide.to);
+            }
+        }
+        Self {
+            slides,
+            fade: captured.map(|(piece, square)| Fade {
+                piece,
+                square,
+                appearing: reverse,
+            }),
+        }
+    }
+
+    /// Whether the animation draws the piece on `square` of the later
+    /// position itself.
+    pub fn covers(&self, square: Square) -> bool {
+        self.slides.iter().any(|slide| slide.to == square)
+            || self
+                .fade
+                .as_ref()
+                .is_some_and(|fade| fade.appearing && fade.square == square)
+    }
+
+    /// Draws the moving pieces `progress` of the way, from 0 to 1.
+    pub fn draw(
+        &self,
+        painter: &egui::Painter,
+        pieces: &mut PieceRenderer,
+        geometry: &BoardGeometry,
+        progress: f32,
+    ) {
+        let size = geometry.square_size;
+        if let Some(fade) = &self.fade {
+            let opacity = if fade.appearing {
+                progress
+            } else {
+                1.0 - progress
+            };
+            let center = geometry.square_center(fade.square);
+            pieces.draw_faded(painter, fade.piece, center, size, opacity);
+        }
+        // Eased so the pieces start and land gently.
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        for slide in &self.slides {
+            let from = geometry.square_center(slide.from);
+            let to = geometry.square_center(slide.to);
+            pieces.draw(painter, slide.piece, from.lerp(to, eased), size);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use chess::clock::{ChessClock, Stage, TimeControl, Timing};
+use chess::engine::piece::PieceColor;
+use egui::{Color32, RichText};
+use std::time::{Duration, Instant};
+
+/// Time below which a clock is drawn as running low.
+const LOW_TIME: Duration = Duration::from_secs(20);
+
+pub fn presets() -> Vec<(&'static str, TimeControl)> {
+    let minutes = |m: u64| Duration::from_secs(m * 60);
+    let seconds = Duration::from_secs;
+    vec![
+        (
+            "Bullet 1+0",
+            TimeControl::fischer(minutes(1), Duration::ZERO),
+        ),
+        ("Blitz 3+2", TimeControl::fischer(minutes(3), seconds(2))),
+        ("Blitz 5+3", TimeControl::fischer(minutes(5), seconds(3))),
+        (
+            "Rapid 15+10",
+            TimeControl::fischer(minutes(15), seconds(10)),
+        ),
+        (
+            "5 min, Bronstein 3 s",
+            TimeControl::bronstein(minutes(5), seconds(3)),
+        ),
+        (
+            "5 min, delay 5 s",
+            TimeControl::delay(minutes(5), seconds(5)),
+        ),
+        (
+            "Classical 40/90, 30 +30 s",
+            TimeControl {
+                stages: vec![
+                    Stage {
+                        moves: Some(40),
+                        time: minutes(90),
+                    },
+                    Stage {
+                        moves: None,
+                        time: minutes(30),
+                    },
+                ],
+                timing: Timing::Increment(seconds(30)),
+            },
+        ),
+        ("Hourglass 1 min", TimeControl::hourglass(minutes(1))),
+    ]
+}
+
+/// `h:mm:ss`, `m:ss`, or seconds with tenths under ten seconds.
+pub fn format_clock(remaining: Duration) -> String {
+    let total = remaining.as_secs();
+    if total >= 3600 {
+        format!("{}:{:02}:{:02}", total / 3600, total / 60 % 60, total % 60)
+    } else if total >= 10 {
+        format!("{}:{:02}", total / 60, total % 60)
+    } else {
+        format!("{:.1}", remaining.as_secs_f64())
+    }
+}
+
+pub fn draw_clock(ui: &mut egui::Ui, clock: &ChessClock, color: PieceColor, now: Instant) {
+    let remaining = clock.remaining(color, now);
+    let active = clock.active() == Some(color) && clock.flagged().is_none();
+    let low = remaining
+        < LOW_TIME
+            .min(clock.stage_time(color) / 10)
+            .max(Duration::from_secs(10))
+        || clock.flagged() == Some(color);
+
+    let fill = match (active, low) {
+        (true, true) => Color32::from_rgb(170, 40, 40),
+        (true, false) => Color32::from_rgb(60, 110, 60),
+        (false, _) => ui.visuals().faint_bg_color,
+    };
+    let text_color = if low && !active {
+        Color32::from_rgb(220, 60, 60)
+    } else if active {
+        Color32::WHITE
+    } else {
+        ui.visuals().text_color()
+    };
+    let name = match color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+
+    egui::Frame::none()
+        .fill(fill)
+        .rounding(4.0)
+        .inner_margin(6.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(name).color(text_color));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(
+                        RichText::new(format_clock(remaining))
+                            .monospace()
+                            .size(22.0)
+                            .color(text_color),
+                    );
+                });
+            });
+        });
+}
@@ -0,0 +1,132 @@
+use crate::new_game::{GameSetup, Side};
+use chess::engine::{
+    chess_move::ChessMove,
+    error::PlayerError,
+    player::{ComputerPlayer, MoveRequest, Player},
+    search::StopSignal,
+    skill::Skill,
+    uci_client::UciClient,
+};
+use chess::game::Game;
+use std::sync::{
+    Arc, Mutex,
+    mpsc::{self, Receiver, TryRecvError},
+};
+use std::thread;
+
+type SharedPlayer = Arc<Mutex<Box<dyn Player + Send>>>;
+
+struct Thinking {
+    // Plies in the game the move was asked for, to spot stale answers.
+    ply: usize,
+    receiver: Receiver<Result<ChessMove, PlayerError>>,
+    stop: StopSignal,
+}
+
+/// The programs playing the computer sides, searching on a background
+/// thread so the GUI keeps drawing while they think.
+pub struct ComputerOpponent {
+    // By `PieceColor as usize`; `None` for a human side.
+    players: [Option<SharedPlayer>; 2],
+    thinking: Option<Thinking>,
+}
+
+impl ComputerOpponent {
+    /// Players for the computer sides of `setup`, starting the external
+    /// engine if one plays.
+    pub fn new(setup: &GameSetup) -> Result<Self, PlayerError> {
+        let white = Self::player(setup, setup.white)?;
+        // Both sides played by the same program share one player.
+        let black = if setup.black == setup.white {
+            white.clone()
+        } else {
+            Self::player(setup, setup.black)?
+        };
+        Ok(Self {
+            players: [white, black],
+            thinking: None,
+        })
+    }
+
+    fn player(setup: &GameSetup, side: Side) -> Result<Option<SharedPlayer>, PlayerError> {
+        let mut player: Box<dyn Player + Send> = match side {
+            Side::Human => return Ok(None),
+            Side::Computer => Box::new(ComputerPlayer::new(Skill::from_level(setup.level))),
+            Side::External => Box::new(UciClient::spawn(&setup.engine_path, &[])?),
+        };
+        player.new_game()?;
+        Ok(Some(Arc::new(Mutex::new(player))))
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.thinking.is_some()
+    }
+
+    /// Starts searching the current position of `game` for the side to
+    /// move. `ctx` is woken up when the move is ready.
+    pub fn start(&mut self, game: &Game, mut request: MoveRequest, ctx: &egui::Context) {
+        self.cancel();
+        let side = game.position().side_to_move();
+        let Some(player) = self.players[side as usize].clone() else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        // A fresh signal for this search alone, so stopping it can neither
+        // be undone nor reach a later one.
+        let stop = StopSignal::default();
+        request.limits.stop = stop.clone();
+        let ply = game.ply_count();
+        let game = game.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let chosen = player.lock().unwrap().choose_move(&game, &request);
+            // The GUI may have moved on; then nobody is listening.
+            if sender.send(chosen).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.thinking = Some(Thinking {
+            ply,
+            receiver,
+            stop,
+        });
+    }
+
+    /// Move found for the position at `ply`, or why none was, once the
+    /// search is over. Answers for another position are dropped.
+    pub fn poll(&mut self, ply: usize) -> Option<Result<ChessMove, PlayerError>> {
+        let thinking = self.thinking.as_ref()?;
+        match thinking.receiver.try_recv() {
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.thinking = None;
+                None
+            }
+            Ok(chosen) => {
+                let current = thinking.ply == ply;
+                self.thinking = None;
+                current.then_some(chosen)
+            }
+        }
+    }
+
+    /// Makes the search return its best move so far.
+    pub fn force_move(&self) {
+        if let Some(thinking) = &self.thinking {
+            thinking.stop.stop();
+        }
+    }
+
+    /// Abandons the running search, e.g. after a takeback.
+    pub fn cancel(&mut self) {
+        if let Some(thinking) = self.thinking.take() {
+            thinking.stop.stop();
+        }
+    }
+}
+
+impl Drop for ComputerOpponent {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
@@ -0,0 +1,393 @@
+use crate::geometry::BoardGeometry;
+use crate::theme::{BoardTheme, PieceRenderer};
+use chess::engine::{
+    board::{BoardFactory, BoardGame, BoardPosition},
+    chess_move::Square,
+    piece::{Piece, PieceColor, PieceType},
+    position::{CastlingRights, Position, possible_castling, possible_en_passant},
+};
+use egui::{Color32, Rect, Vec2};
+
+const PALETTE: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+const PALETTE_SQUARE: f32 = 36.0;
+
+pub enum EditorAction {
+    Play(Position),
+    Cancel,
+}
+
+/// A piece picked up with the left button, from the board or the palette.
+#[derive(Clone, Copy)]
+struct Press {
+    square: Option<Square>,
+    piece: Option<Piece>,
+    moved: bool,
+}
+
+/// Board setup mode: pieces are placed from a palette or moved around, and
+/// the rest of the position is set with the controls beside the board.
+pub struct PositionEditor {
+    board: BoardGame,
+    side_to_move: PieceColor,
+    // Rights asked for; those the board does not allow are left out.
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    // Piece placed by a click on the board; `None` erases.
+    tool: Option<Piece>,
+    press: Option<Press>,
+    fen_text: String,
+    fen_error: Option<String>,
+}
+
+impl PositionEditor {
+    pub fn new(position: &Position) -> Self {
+        let mut editor = Self {
+            board: *position.board(),
+            side_to_move: position.side_to_move(),
+            castling: position.castling_rights(),
+            en_passant: position.en_passant(),
+            tool: Some(Piece::new(PieceType::Pawn, PieceColor::White)),
+            press: None,
+            fen_text: String::new(),
+            fen_error: None,
+        };
+        editor.fen_text = editor.position().to_fen();
+        editor
+    }
+
+    /// The position being set up, with the castling and en passant choices
+    /// the board still allows.
+    pub fn position(&self) -> Position {
+        let mut castling = CastlingRights::NONE;
+        let possible = possible_castling(&self.board);
+        for right in [
+            CastlingRights::WHITE_KINGSIDE,
+            CastlingRights::WHITE_QUEENSIDE,
+            CastlingRights::BLACK_KINGSIDE,
+            CastlingRights::BLACK_QUEENSIDE,
+        ] {
+            if self.castling.contains(right) && possible.contains(right) {
+                castling.insert(right);
+            }
+        }
+        let en_passant = self
+            .en_passant
+            .filter(|square| possible_en_passant(&self.board, self.side_to_move).contains(square));
+        Position::from_parts(self.board, self.side_to_move, castling, en_passant, 0, 1)
+    }
+
+    fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.row()][square.col()]
+    }
+
+    fn set_piece(&mut self, square: Square, piece: Option<Piece>) {
+        self.board[square.row()][square.col()] = piece;
+        self.sync_fen();
+    }
+
+    fn sync_fen(&mut self) {
+        self.fen_text = self.position().to_fen();
+        self.fen_error = None;
+    }
+
+    fn load_fen(&mut self) {
+        match Position::from_fen(&self.fen_text) {
+            Ok(position) => {
+                let tool = self.tool;
+                *self = Self::new(&position);
+                self.tool = tool;
+            }
+            Err(e) => self.fen_error = Some(e.to_string()),
+        }
+    }
+
+    fn reset_board(&mut self, setup: BoardPosition) {
+        self.board = BoardFactory::create(setup);
+        self.side_to_move = PieceColor::White;
+        self.castling = possible_castling(&self.board);
+        self.en_passant = None;
+        self.sync_fen();
+    }
+
+    pub fn draw_board(
+        &mut self,
+        ui: &mut egui::Ui,
+        flipped: bool,
+        pieces: &mut PieceRenderer,
+        theme: &BoardTheme,
+    ) {
+        let board_size = ui.available_width().min(ui.available_height());
+        let board_rect = Rect::from_min_size(ui.cursor().min, Vec2::splat(board_size));
+        ui.allocate_rect(board_rect, egui::Sense::click_and_drag());
+        let geometry = BoardGeometry::new(board_rect, flipped);
+        let painter = ui.painter();
+
+        let dragged_from = self
+            .press
+            .filter(|press| press.moved)
+            .and_then(|press| press.square);
+        for index in 0..64u8 {
+            let square = Square::try_from(index).expect("index is below 64");
+            let rect = geometry.square_rect(square);
+            let is_white = (square.row() + square.col()) % 2 == 0;
+            painter.rect_filled(rect, 0.0, if is_white { theme.light } else { theme.dark });
+            if let Some(piece) = self.piece_at(square)
+                && dragged_from != Some(square)
+            {
+                pieces.draw(painter, piece, rect.center(), geometry.square_size);
+            }
+        }
+
+        let (pressed, released, right_clicked, dragging, pointer) = ui.ctx().input(|i| {
+            (
+                i.pointer.primary_pressed(),
+                i.pointer.primary_released(),
+                i.pointer.secondary_pressed(),
+                i.pointer.is_decidedly_dragging(),
+                i.pointer.interact_pos(),
+            )
+        });
+        let square = pointer.and_then(|pos| geometry.square_at(pos));
+
+        if pressed && let Some(square) = square {
+            self.press = Some(Press {
+                square: Some(square),
+                piece: self.piece_at(square),
+                moved: false,
+            });
+        }
+        if right_clicked && let Some(square) = square {
+            self.set_piece(square, None);
+        }
+        if let Some(press) = &mut self.press {
+            press.moved |= dragging;
+        }
+        if released && let Some(press) = self.press.take() {
+            match (press.moved, press.square, square) {
+                // A click applies the palette tool; clicking the same piece
+                // again takes it off.
+                (false, Some(from), Some(to)) if from == to => {
+                    let piece = self.tool.filter(|&tool| self.piece_at(to) != Some(tool));
+                    self.set_piece(to, piece);
+                }
+                (true, from, to) => {
+                    if let Some(from) = from {
+                        self.set_piece(from, None);
+                    }
+                    // Dropped off the board, a piece from the board is gone.
+                    if let (Some(to), Some(piece)) = (to, press.piece) {
+                        self.set_piece(to, Some(piece));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(press) = self.press
+            && press.moved
+            && let Some(piece) = press.piece
+            && let Some(pos) = ui.ctx().pointer_hover_pos()
+        {
+            let layer = egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("editor_drag"));
+            pieces.draw(
+                &ui.ctx().layer_painter(layer),
+                piece,
+                pos,
+                geometry.square_size * 1.15,
+            );
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn draw_palette(&mut self, ui: &mut egui::Ui, pieces: &mut PieceRenderer) {
+        for color in [PieceColor::White, PieceColor::Black] {
+            ui.horizontal(|ui| {
+                for piece_type in PALETTE {
+                    let piece = Piece::new(piece_type, color);
+                    self.palette_button(ui, pieces, Some(piece));
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            self.palette_button(ui, pieces, None);
+            ui.label(match self.tool {
+                Some(_) => "Click to place, drag to move",
+                None => "Click to erase",
+            });
+        });
+    }
+
+    /// One palette entry: pressing it selects the tool and can start
+    /// dragging the piece onto the board.
+    fn palette_button(
+        &mut self,
+        ui: &mut egui::Ui,
+        pieces: &mut PieceRenderer,
+        piece: Option<Piece>,
+    ) {
+        let (rect, response) =
+            ui.allocate_exact_size(Vec2::splat(PALETTE_SQUARE), egui::Sense::click_and_drag());
+        let selected = self.tool == piece;
+        let fill = if selected {
+            Color32::from_rgb(186, 202, 68)
+        } else {
+            Color32::from_gray(150)
+        };
+        ui.painter().rect_filled(rect, 4.0, fill);
+        match piece {
+            Some(piece) => pieces.draw(ui.painter(), piece, rect.center(), PALETTE_SQUARE),
+            None => {
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "✖",
+                    egui::FontId::proportional(20.0),
+                    Color32::from_gray(40),
+                );
+            }
+        }
+        let response = response.on_hover_text(match piece {
+            Some(_) => "Place this piece",
+            None => "Eraser",
+        });
+        if response.is_pointer_button_down_on() && self.press.is_none() {
+            self.tool = piece;
+            self.press = Some(Press {
+                square: None,
+                piece,
+                moved: false,
+            });
+        }
+    }
+
+    pub fn draw_controls(
+        &mut self,
+        ui: &mut egui::Ui,
+        pieces: &mut PieceRenderer,
+    ) -> Option<EditorAction> {
+        ui.heading("Set up position");
+        self.draw_palette(ui, pieces);
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if ui.button("Starting position").clicked() {
+                self.reset_board(BoardPosition::Standard);
+            }
+            if ui.button("Clear board").clicked() {
+                self.reset_board(BoardPosition::Empty);
+            }
+        });
+
+        let before = (self.side_to_move, self.castling, self.en_passant);
+        ui.horizontal(|ui| {
+            ui.label("To move:");
+            ui.radio_value(&mut self.side_to_move, PieceColor::White, "White");
+            ui.radio_value(&mut self.side_to_move, PieceColor::Black, "Black");
+        });
+
+        let possible = possible_castling(&self.board);
+        egui::Grid::new("editor_castling").show(ui, |ui| {
+            ui.label("Castling:");
+            ui.label("O-O");
+            ui.label("O-O-O");
+            ui.end_row();
+            for (name, color) in [("White", PieceColor::White), ("Black", PieceColor::Black)] {
+                ui.label(name);
+                for right in [
+                    CastlingRights::kingside(color),
+                    CastlingRights::queenside(color),
+                ] {
+                    let mut allowed = self.castling.contains(right) && possible.contains(right);
+                    if ui
+                        .add_enabled(
+                            possible.contains(right),
+                            egui::Checkbox::without_text(&mut allowed),
+                        )
+                        .changed()
+                    {
+                        if allowed {
+                            self.castling.insert(right);
+                        } else {
+                            self.castling.remove(right);
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+        let candidates = possible_en_passant(&self.board, self.side_to_move);
+        let current = self.en_passant.filter(|square| candidates.contains(square));
+        ui.horizontal(|ui| {
+            ui.label("En passant:");
+            ui.add_enabled_ui(!candidates.is_empty(), |ui| {
+                egui::ComboBox::from_id_source("editor_en_passant")
+                    .selected_text(current.map_or("-".to_string(), |square| square.to_string()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.en_passant, None, "-");
+                        for square in candidates {
+                            ui.selectable_value(
+                                &mut self.en_passant,
+                                Some(square),
+                                square.to_string(),
+                            );
+                        }
+                    });
+            });
+        });
+        if (self.side_to_move, self.castling, self.en_passant) != before {
+            self.sync_fen();
+        }
+
+        ui.separator();
+        ui.label("FEN");
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.fen_text)
+                .desired_width(f32::INFINITY)
+                .font(egui::TextStyle::Monospace),
+        );
+        ui.horizontal(|ui| {
+            if ui.button("Load FEN").clicked() {
+                self.load_fen();
+            }
+            if ui.button("Copy FEN").clicked() {
+                let fen = self.position().to_fen();
+                ui.output_mut(|output| output.copied_text = fen);
+            }
+        });
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            self.load_fen();
+        }
+        if let Some(error) = &self.fen_error {
+            ui.colored_label(Color32::from_rgb(220, 60, 60), error);
+        }
+
+        ui.separator();
+        let position = self.position();
+        let validation = position.validate();
+        match &validation {
+            Ok(()) => ui.colored_label(Color32::from_rgb(60, 160, 60), "Position is legal"),
+            Err(e) => ui.colored_label(Color32::from_rgb(220, 60, 60), e.to_string()),
+        };
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(validation.is_ok(), egui::Button::new("Play from here"))
+                .clicked()
+            {
+                action = Some(EditorAction::Play(position));
+            }
+            if ui.button("Cancel").clicked() {
+                action = Some(EditorAction::Cancel);
+            }
+        });
+        action
+    }
+}
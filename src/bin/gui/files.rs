@@ -0,0 +1,118 @@
+use chess::engine::position::Position;
+use chess::pgn::{PgnGame, parse_pgn};
+use std::fs;
+use std::path::Path;
+
+/// What a piece of text from a file or the clipboard turned out to hold.
+pub enum Imported {
+    Position(Position),
+    Games(Vec<PgnGame>),
+}
+
+/// Reads `text` as a FEN position, or otherwise as PGN games.
+pub fn import_text(text: &str) -> Result<Imported, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("There is nothing to load.".to_string());
+    }
+    match Position::from_fen(text) {
+        Ok(position) => {
+            position
+                .validate()
+                .map_err(|e| format!("Illegal position: {}", e))?;
+            return Ok(Imported::Position(position));
+        }
+        // A board with its ranks split by slashes and no move numbers is
+        // meant as FEN, so its error says more than the PGN one would.
+        Err(e) if text.contains('/') && !text.contains('.') => {
+            return Err(format!("Invalid FEN: {}", e));
+        }
+        Err(_) => {}
+    }
+    let games = parse_pgn(text).map_err(|e| format!("Invalid PGN: {}", e))?;
+    if games.is_empty() {
+        return Err("No game found.".to_string());
+    }
+    Ok(Imported::Games(games))
+}
+
+pub fn read_pgn_file(path: &Path) -> Result<Vec<PgnGame>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let games = parse_pgn(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if games.is_empty() {
+        return Err(format!("{} holds no games.", path.display()));
+    }
+    Ok(games)
+}
+
+pub fn write_pgn_file(path: &Path, pgn: &str) -> Result<(), String> {
+    fs::write(path, pgn).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+pub fn read_clipboard() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| format!("Cannot read the clipboard: {}", e))
+}
+
+pub enum PickerAction {
+    Load(PgnGame),
+    Cancel,
+}
+
+/// Window listing the games of a PGN file with several of them, to choose
+/// the one to load.
+pub struct GamePicker {
+    games: Vec<PgnGame>,
+}
+
+impl GamePicker {
+    pub fn new(games: Vec<PgnGame>) -> Self {
+        Self { games }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PickerAction> {
+        let mut chosen = None;
+        let mut cancel = false;
+        egui::Window::new("Choose a game")
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("The file holds {} games.", self.games.len()));
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        for (index, pgn) in self.games.iter().enumerate() {
+                            if ui.selectable_label(false, game_label(index, pgn)).clicked() {
+                                chosen = Some(index);
+                            }
+                        }
+                    });
+                ui.add_space(8.0);
+                cancel = ui.button("Cancel").clicked();
+            });
+        if cancel {
+            return Some(PickerAction::Cancel);
+        }
+        chosen.map(|index| PickerAction::Load(self.games.swap_remove(index)))
+    }
+}
+
+/// `3. Carlsen – Caruana, 1-0 (World Championship, 2018.11.09)`, leaving
+/// out tags that are unknown.
+fn game_label(index: usize, pgn: &PgnGame) -> String {
+    let known = |name| pgn.tag(name).filter(|value| !value.contains('?'));
+    let mut label = format!(
+        "{}. {} – {}, {}",
+        index + 1,
+        known("White").unwrap_or("?"),
+        known("Black").unwrap_or("?"),
+        pgn.tag("Result").unwrap_or("*"),
+    );
+    let details: Vec<&str> = ["Event", "Date"].into_iter().filter_map(known).collect();
+    if !details.is_empty() {
+        label.push_str(&format!(" ({})", details.join(", ")));
+    }
+    label
+}
@@ -0,0 +1,55 @@
+use chess::engine::chess_move::Square;
+use egui::{Pos2, Rect, Vec2};
+
+/// Where each square of the board is drawn on screen, taking the board
+/// orientation into account.
+#[derive(Clone, Copy, Debug)]
+pub struct BoardGeometry {
+    pub rect: Rect,
+    pub square_size: f32,
+    /// Black at the bottom when set.
+    pub flipped: bool,
+}
+
+impl BoardGeometry {
+    pub fn new(rect: Rect, flipped: bool) -> Self {
+        Self {
+            rect,
+            square_size: rect.width() / 8.0,
+            flipped,
+        }
+    }
+
+    /// Column and row of `square` on screen, counted from the top left.
+    fn screen_cell(&self, square: Square) -> (usize, usize) {
+        if self.flipped {
+            (7 - square.col(), 7 - square.row())
+        } else {
+            (square.col(), square.row())
+        }
+    }
+
+    pub fn square_rect(&self, square: Square) -> Rect {
+        let (col, row) = self.screen_cell(square);
+        let min = self.rect.min + Vec2::new(col as f32, row as f32) * self.square_size;
+        Rect::from_min_size(min, Vec2::splat(self.square_size))
+    }
+
+    pub fn square_center(&self, square: Square) -> Pos2 {
+        self.square_rect(square).center()
+    }
+
+    pub fn square_at(&self, pos: Pos2) -> Option<Square> {
+        if !self.rect.contains(pos) {
+            return None;
+        }
+        let col = (((pos.x - self.rect.min.x) / self.square_size) as u8).min(7);
+        let row = (((pos.y - self.rect.min.y) / self.square_size) as u8).min(7);
+        let (row, col) = if self.flipped {
+            (7 - row, 7 - col)
+        } else {
+            (row, col)
+        };
+        Square::try_from((row, col)).ok()
+    }
+}
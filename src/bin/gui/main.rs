@@ -0,0 +1,1765 @@
+use analysis::Analyzer;
+use animation::MoveAnimation;
+use chess::clock::{ChessClock, TimeControl, timeout_outcome};
+use chess::engine::{
+    chess_move::{ChessMove, Square},
+    piece::{Piece, PieceColor, PieceType},
+    player::MoveRequest,
+    position::Position,
+    search::SearchLimits,
+};
+use chess::game::{Game, GameResult, Outcome, Termination, material_points};
+use chess::markup::{MarkColor, Markup};
+use chess::pgn::{PgnGame, parse_pgn};
+use chess::tree::{GameTree, NodeId};
+use computer::ComputerOpponent;
+use editor::{EditorAction, PositionEditor};
+use egui::{Color32, Rect, Vec2};
+use files::{GamePicker, Imported, PickerAction};
+use geometry::BoardGeometry;
+use move_list::{MOVE_ASSESSMENTS, MoveList, MoveListAction};
+use new_game::{GameSetup, NewGameDialog};
+use persistence::SavedState;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use theme::{BOARD_THEMES, BoardTheme, PieceRenderer, PieceSet};
+
+mod analysis;
+mod animation;
+mod clock_view;
+mod computer;
+mod editor;
+mod files;
+mod geometry;
+mod move_list;
+mod new_game;
+mod persistence;
+mod theme;
+
+pub struct ChessUi {
+    // The line of `tree` being played or reviewed.
+    game: Game,
+    // Every move played or loaded, with variations and annotations.
+    tree: GameTree,
+    // Node of each position of `game`, starting with the root.
+    line: Vec<NodeId>,
+    selected_position: Option<Square>,
+    dragging_piece: Option<(Square, egui::Pos2)>,
+    // Set when the pressed piece was already selected, so that releasing
+    // it without dragging unselects it.
+    deselect_on_release: bool,
+    // Pawn move waiting for the user to pick the promotion piece.
+    pending_promotion: Option<(Square, Square)>,
+    // Earlier position shown from the move list; `None` follows the game.
+    view_ply: Option<usize>,
+    // Moves taken back with undo, most recent last.
+    redo_stack: Vec<ChessMove>,
+    flipped: bool,
+    // Turn the board so the human's side is at the bottom in games against
+    // the computer.
+    auto_orient: bool,
+    show_coordinates: bool,
+    // Slide pieces to their new squares instead of moving them at once.
+    animate_moves: bool,
+    // Length of a move animation in seconds.
+    animation_time: f32,
+    // Node whose position the board last drew, to animate the move from
+    // it to the next one.
+    shown: Option<NodeId>,
+    animation: Option<MoveAnimation>,
+    // Flipped to start each animation over with egui's animation timer.
+    animation_toggle: bool,
+    clock: Option<ChessClock>,
+    // Set with the node where a flag falls; the rules alone never end a
+    // game on time.
+    clock_outcome: Option<(NodeId, Outcome)>,
+    // Node at which the game-over dialog was closed, so it stays closed
+    // while the finished game is reviewed.
+    dismissed_game_over: Option<NodeId>,
+    pgn_copied: bool,
+    setup: GameSetup,
+    // Present while the computer plays at least one side.
+    computer: Option<ComputerOpponent>,
+    new_game_dialog: NewGameDialog,
+    // Present while analysis mode is on.
+    analyzer: Option<Analyzer>,
+    // Arrows and circles drawn by the user, by the node they were drawn on.
+    markup: BTreeMap<NodeId, Markup>,
+    // Square where the right button went down for the arrow being drawn.
+    markup_start: Option<Square>,
+    // Open while a position is being set up.
+    editor: Option<PositionEditor>,
+    pieces: PieceRenderer,
+    board_theme: BoardTheme,
+    show_settings: bool,
+    // Open while choosing a game from a PGN file with several.
+    game_picker: Option<GamePicker>,
+    // Why the computer could not play or a file could not be loaded or
+    // saved, shown until dismissed.
+    error: Option<String>,
+}
+
+const LAST_MOVE_TINT: Color32 = Color32::from_rgba_premultiplied(150, 150, 0, 100);
+const CHECK_TINT: Color32 = Color32::from_rgba_premultiplied(190, 20, 20, 170);
+const BEST_MOVE_ARROW: Color32 = Color32::from_rgba_premultiplied(20, 85, 30, 170);
+const EVAL_BAR_WIDTH: f32 = 24.0;
+// Rows above and below the board showing the pieces each side has taken.
+const MATERIAL_STRIP_HEIGHT: f32 = 24.0;
+
+fn mark_color(color: MarkColor) -> Color32 {
+    match color {
+        MarkColor::Green => Color32::from_rgba_unmultiplied(21, 120, 27, 170),
+        MarkColor::Red => Color32::from_rgba_unmultiplied(190, 30, 30, 170),
+        MarkColor::Yellow => Color32::from_rgba_unmultiplied(230, 160, 0, 170),
+        MarkColor::Blue => Color32::from_rgba_unmultiplied(0, 48, 136, 170),
+    }
+}
+
+const PROMOTION_CHOICES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+impl Default for ChessUi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChessUi {
+    pub fn new() -> Self {
+        let setup = GameSetup::default();
+        Self {
+            game: Game::new(),
+            tree: GameTree::new(Position::default()),
+            line: vec![GameTree::ROOT],
+            selected_position: None,
+            dragging_piece: None,
+            deselect_on_release: false,
+            pending_promotion: None,
+            view_ply: None,
+            redo_stack: Vec::new(),
+            flipped: false,
+            auto_orient: true,
+            show_coordinates: true,
+            animate_moves: true,
+            animation_time: 0.2,
+            shown: None,
+            animation: None,
+            animation_toggle: false,
+            clock: None,
+            clock_outcome: None,
+            dismissed_game_over: None,
+            pgn_copied: false,
+            new_game_dialog: NewGameDialog::new(&setup),
+            setup,
+            computer: None,
+            analyzer: None,
+            markup: BTreeMap::new(),
+            markup_start: None,
+            editor: None,
+            pieces: PieceRenderer::new(PieceSet::Classic),
+            board_theme: BOARD_THEMES[0],
+            show_settings: false,
+            game_picker: None,
+            error: None,
+        }
+    }
+
+    /// File letters along the bottom edge and rank numbers along the left
+    /// edge, in the colour of the opposite square.
+    fn draw_coordinates(painter: &egui::Painter, geometry: &BoardGeometry, theme: &BoardTheme) {
+        let font = egui::FontId::proportional(geometry.square_size * 0.18);
+        let margin = geometry.square_size * 0.05;
+        for index in 0..64u8 {
+            let square = Square::try_from(index).expect("index is below 64");
+            let rect = geometry.square_rect(square);
+            let is_white = (square.row() + square.col()) % 2 == 0;
+            let color = if is_white { theme.dark } else { theme.light };
+            let name = square.to_string();
+            let (file, rank) = name.split_at(1);
+            if (rect.max.y - geometry.rect.max.y).abs() < 0.5 {
+                painter.text(
+                    rect.right_bottom() + Vec2::new(-margin, -margin),
+                    egui::Align2::RIGHT_BOTTOM,
+                    file,
+                    font.clone(),
+                    color,
+                );
+            }
+            if (rect.min.x - geometry.rect.min.x).abs() < 0.5 {
+                painter.text(
+                    rect.left_top() + Vec2::new(margin, margin),
+                    egui::Align2::LEFT_TOP,
+                    rank,
+                    font.clone(),
+                    color,
+                );
+            }
+        }
+    }
+
+    /// Legal moves of the piece on `from`, as found by the move generator.
+    fn legal_targets(&self, from: Square) -> Vec<ChessMove> {
+        self.displayed_position()
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == from)
+            .collect()
+    }
+
+    /// Arrow from the centre of `from` to the centre of `to`, with the head
+    /// ending short of the target's centre.
+    fn draw_arrow(
+        painter: &egui::Painter,
+        geometry: &BoardGeometry,
+        from: Square,
+        to: Square,
+        color: Color32,
+    ) {
+        if from == to {
+            return;
+        }
+        let start = geometry.square_center(from);
+        let end = geometry.square_center(to);
+        let direction = (end - start).normalized();
+        let normal = direction.rot90();
+        let width = geometry.square_size * 0.16;
+        let head_length = geometry.square_size * 0.4;
+        let tip = end - direction * geometry.square_size * 0.15;
+        let head_base = tip - direction * head_length;
+        painter.line_segment(
+            [start + direction * geometry.square_size * 0.2, head_base],
+            egui::Stroke::new(width, color),
+        );
+        painter.add(egui::Shape::convex_polygon(
+            vec![
+                tip,
+                head_base + normal * width * 1.6,
+                head_base - normal * width * 1.6,
+            ],
+            color,
+            egui::Stroke::NONE,
+        ));
+    }
+
+    /// Vertical bar showing White's share of the evaluation, White's part
+    /// at the bottom of the board.
+    fn draw_eval_bar(
+        painter: &egui::Painter,
+        rect: Rect,
+        snapshot: Option<&analysis::AnalysisSnapshot>,
+        flipped: bool,
+    ) {
+        let white_score = snapshot.and_then(|snapshot| snapshot.white_score());
+        let share = white_score.map_or(0.5, analysis::white_share);
+        painter.rect_filled(rect, 2.0, Color32::from_gray(40));
+        let white_height = rect.height() * share;
+        let white_rect = if flipped {
+            Rect::from_min_max(rect.min, egui::pos2(rect.max.x, rect.min.y + white_height))
+        } else {
+            Rect::from_min_max(egui::pos2(rect.min.x, rect.max.y - white_height), rect.max)
+        };
+        painter.rect_filled(white_rect, 2.0, Color32::from_gray(235));
+        if let Some(score) = white_score {
+            // The number sits on the side that is ahead.
+            let white_ahead = score >= 0;
+            let at_bottom = white_ahead != flipped;
+            let (anchor, position, color) = if at_bottom {
+                (
+                    egui::Align2::CENTER_BOTTOM,
+                    rect.center_bottom() - Vec2::new(0.0, 4.0),
+                    Color32::from_gray(40),
+                )
+            } else {
+                (
+                    egui::Align2::CENTER_TOP,
+                    rect.center_top() + Vec2::new(0.0, 4.0),
+                    Color32::from_gray(235),
+                )
+            };
+            let text = analysis::format_score(score.abs())
+                .trim_start_matches('+')
+                .to_string();
+            painter.text(
+                position,
+                anchor,
+                text,
+                egui::FontId::proportional(10.0),
+                color,
+            );
+        }
+    }
+
+    /// Green by default; Shift for red, Alt for blue, and Ctrl or both
+    /// Shift and Alt for yellow.
+    fn markup_color(modifiers: &egui::Modifiers) -> MarkColor {
+        match (modifiers.shift, modifiers.alt, modifiers.command) {
+            (_, _, true) | (true, true, _) => MarkColor::Yellow,
+            (true, false, _) => MarkColor::Red,
+            (false, true, _) => MarkColor::Blue,
+            (false, false, false) => MarkColor::Green,
+        }
+    }
+
+    /// Right-click circles a square, right-drag draws an arrow; doing it
+    /// again in the same colour removes it.
+    fn handle_markup_input(&mut self, ctx: &egui::Context, geometry: &BoardGeometry) {
+        let (pressed, released, square, color) = ctx.input(|i| {
+            (
+                i.pointer.secondary_pressed(),
+                i.pointer.secondary_released(),
+                i.pointer
+                    .interact_pos()
+                    .and_then(|pos| geometry.square_at(pos)),
+                Self::markup_color(&i.modifiers),
+            )
+        });
+        if pressed {
+            self.markup_start = square;
+        }
+        if released && let Some(from) = self.markup_start.take() {
+            let markup = self.markup.entry(self.displayed_node()).or_default();
+            match square {
+                Some(to) if to == from => markup.toggle_highlight(from, color),
+                Some(to) => markup.toggle_arrow(from, to, color),
+                None => {}
+            }
+        }
+        if self.markup_start.is_some() {
+            ctx.request_repaint();
+        }
+    }
+
+    fn draw_markup(painter: &egui::Painter, geometry: &BoardGeometry, markup: &Markup) {
+        for highlight in &markup.highlights {
+            painter.circle_stroke(
+                geometry.square_center(highlight.square),
+                geometry.square_size * 0.45,
+                egui::Stroke::new(geometry.square_size * 0.07, mark_color(highlight.color)),
+            );
+        }
+        for arrow in &markup.arrows {
+            Self::draw_arrow(
+                painter,
+                geometry,
+                arrow.from,
+                arrow.to,
+                mark_color(arrow.color),
+            );
+        }
+    }
+
+    /// Pieces taken by each side in rows along the board edge nearest that
+    /// side, smallest first, with the material lead of the side ahead.
+    fn draw_material(&mut self, painter: &egui::Painter, board_rect: Rect) {
+        let material = self.game.material(self.displayed_ply());
+        let bottom = if self.flipped {
+            PieceColor::Black
+        } else {
+            PieceColor::White
+        };
+        let top_strip = Rect::from_min_max(
+            board_rect.left_top() - Vec2::new(0.0, MATERIAL_STRIP_HEIGHT),
+            board_rect.right_top(),
+        );
+        let bottom_strip = Rect::from_min_max(
+            board_rect.left_bottom(),
+            board_rect.right_bottom() + Vec2::new(0.0, MATERIAL_STRIP_HEIGHT),
+        );
+        for (color, strip) in [(bottom.opposite(), top_strip), (bottom, bottom_strip)] {
+            let mut captured = material.captured_by(color).to_vec();
+            captured.sort_by_key(|&piece_type| (material_points(piece_type), piece_type.index()));
+            let size = strip.height();
+            let mut x = strip.left() + size * 0.5;
+            for (index, &piece_type) in captured.iter().enumerate() {
+                // Pieces of one kind overlap; a new kind leaves a gap.
+                if index > 0 && captured[index - 1] != piece_type {
+                    x += size * 0.35;
+                }
+                let piece = Piece::new(piece_type, color.opposite());
+                self.pieces
+                    .draw(painter, piece, egui::pos2(x, strip.center().y), size);
+                x += size * 0.45;
+            }
+            let lead = match color {
+                PieceColor::White => material.balance,
+                PieceColor::Black => -material.balance,
+            };
+            if lead > 0 {
+                painter.text(
+                    egui::pos2(x + size * 0.25, strip.center().y),
+                    egui::Align2::LEFT_CENTER,
+                    format!("+{}", lead),
+                    egui::FontId::proportional(size * 0.6),
+                    painter.ctx().style().visuals.text_color(),
+                );
+            }
+        }
+    }
+
+    /// Starts animating when the displayed position is one move before or
+    /// after the one drawn last, and returns how far the running animation
+    /// has got, from 0 to 1.
+    fn update_animation(&mut self, ctx: &egui::Context) -> f32 {
+        let node = self.displayed_node();
+        if let Some(shown) = self.shown.replace(node)
+            && shown != node
+        {
+            let tree = &self.tree;
+            self.animation = if !self.animate_moves {
+                None
+            } else if tree.parent(node) == Some(shown) {
+                let mv = tree.mv(node).expect("a child has a move");
+                Some(MoveAnimation::new(tree.position(shown), mv, false))
+            } else if tree.parent(shown) == Some(node) {
+                let mv = tree.mv(shown).expect("a child has a move");
+                Some(MoveAnimation::new(tree.position(node), mv, true))
+            } else {
+                None
+            };
+            if self.animation.is_some() {
+                self.animation_toggle = !self.animation_toggle;
+            }
+        }
+        let value = ctx.animate_bool_with_time(
+            egui::Id::new("move_animation"),
+            self.animation_toggle,
+            self.animation_time,
+        );
+        let progress = if self.animation_toggle {
+            value
+        } else {
+            1.0 - value
+        };
+        if progress >= 1.0 {
+            self.animation = None;
+        }
+        progress
+    }
+
+    fn draw_board(&mut self, ui: &mut egui::Ui) {
+        let progress = self.update_animation(ui.ctx());
+        let snapshot = self
+            .analyzer
+            .as_ref()
+            .and_then(|analyzer| analyzer.snapshot());
+        let bar_space = if self.analyzer.is_some() {
+            EVAL_BAR_WIDTH + 6.0
+        } else {
+            0.0
+        };
+        let board_size = (ui.available_width() - bar_space)
+            .min(ui.available_height() - 2.0 * MATERIAL_STRIP_HEIGHT);
+        let square_size = board_size / 8.0;
+        let origin = ui.cursor().min;
+        let board_rect = Rect::from_min_size(
+            origin + Vec2::new(bar_space, MATERIAL_STRIP_HEIGHT),
+            Vec2::new(board_size, board_size),
+        );
+
+        ui.allocate_rect(
+            board_rect
+                .expand2(Vec2::new(0.0, MATERIAL_STRIP_HEIGHT))
+                .union(Rect::from_min_size(origin, Vec2::ZERO)),
+            egui::Sense::click_and_drag(),
+        );
+        let geometry = BoardGeometry::new(board_rect, self.flipped);
+
+        let painter = ui.painter();
+        if self.analyzer.is_some() {
+            let bar_rect = Rect::from_min_size(
+                origin + Vec2::new(0.0, MATERIAL_STRIP_HEIGHT),
+                Vec2::new(EVAL_BAR_WIDTH, board_size),
+            );
+            Self::draw_eval_bar(painter, bar_rect, snapshot.as_ref(), self.flipped);
+        }
+        self.draw_material(painter, board_rect);
+        let position = *self.displayed_position();
+        let board = position.board();
+        let current_player = position.side_to_move();
+        // A press without movement is a click and leaves the piece in place.
+        let is_dragging = ui.ctx().input(|i| i.pointer.is_decidedly_dragging());
+        let last_move = self.tree.mv(self.displayed_node());
+        let king_in_check = position
+            .is_in_check(current_player)
+            .then(|| Self::king_square(&position, current_player))
+            .flatten();
+
+        for index in 0..64u8 {
+            let square = Square::try_from(index).expect("index is below 64");
+            let square_rect = geometry.square_rect(square);
+            let is_white = (square.row() + square.col()) % 2 == 0;
+            let square_color = if is_white {
+                self.board_theme.light
+            } else {
+                self.board_theme.dark
+            };
+
+            painter.rect_filled(square_rect, 0.0, square_color);
+            if last_move.is_some_and(|mv| mv.from == square || mv.to == square) {
+                painter.rect_filled(square_rect, 0.0, LAST_MOVE_TINT);
+            }
+            if king_in_check == Some(square) {
+                painter.circle_filled(square_rect.center(), square_size * 0.48, CHECK_TINT);
+            }
+
+            if self.selected_position == Some(square) {
+                painter.rect_stroke(square_rect, 0.0, egui::Stroke::new(1.0, Color32::YELLOW));
+            }
+
+            if let Some(piece) = position.piece_at(square) {
+                let is_dragging = is_dragging
+                    && self
+                        .dragging_piece
+                        .is_some_and(|(drag_sq, _)| drag_sq == square);
+
+                let is_animated = self
+                    .animation
+                    .as_ref()
+                    .is_some_and(|animation| animation.covers(square));
+
+                if !is_dragging && !is_animated {
+                    self.pieces
+                        .draw(painter, piece, square_rect.center(), square_size);
+                }
+            }
+        }
+        if let Some(animation) = &self.animation {
+            animation.draw(painter, &mut self.pieces, &geometry, progress);
+        }
+        if self.show_coordinates {
+            Self::draw_coordinates(painter, &geometry, &self.board_theme);
+        }
+        if let Some(best) = snapshot
+            .as_ref()
+            .filter(|snapshot| snapshot.position.hash() == position.hash())
+            .and_then(|snapshot| snapshot.best_move())
+        {
+            Self::draw_arrow(painter, &geometry, best.from, best.to, BEST_MOVE_ARROW);
+        }
+        self.handle_markup_input(ui.ctx(), &geometry);
+        if let Some(markup) = self.markup.get(&self.displayed_node()) {
+            Self::draw_markup(painter, &geometry, markup);
+        }
+        if let Some(from) = self.markup_start
+            && let Some(to) = ui
+                .ctx()
+                .pointer_hover_pos()
+                .and_then(|pos| geometry.square_at(pos))
+            && to != from
+        {
+            let color = ui.ctx().input(|i| Self::markup_color(&i.modifiers));
+            Self::draw_arrow(painter, &geometry, from, to, mark_color(color));
+        }
+
+        // Dots on empty targets and rings around capturable pieces for the
+        // piece being moved.
+        let active = self
+            .dragging_piece
+            .map(|(square, _)| square)
+            .or(self.selected_position);
+        if let Some(from) = active {
+            let hint_color = Color32::from_black_alpha(70);
+            for mv in self.legal_targets(from) {
+                let center = geometry.square_center(mv.to);
+                if position.is_capture(&mv) {
+                    painter.circle_stroke(
+                        center,
+                        square_size * 0.44,
+                        egui::Stroke::new(square_size * 0.08, hint_color),
+                    );
+                } else {
+                    painter.circle_filled(center, square_size * 0.15, hint_color);
+                }
+            }
+        }
+
+        // While the clock runs earlier positions are only for viewing;
+        // otherwise a move there starts a variation. Nothing moves once the
+        // game is over.
+        let computer_side =
+            self.computer.is_some() && self.setup.side(current_player).is_computer();
+        if (self.view_ply.is_some() && self.clock_running())
+            || self.displayed_outcome().is_some()
+            || computer_side
+        {
+            return;
+        }
+
+        let (pressed, released, pointer) = ui.ctx().input(|i| {
+            (
+                i.pointer.primary_pressed(),
+                i.pointer.primary_released(),
+                i.pointer.interact_pos(),
+            )
+        });
+        let pointer_square = pointer.and_then(|pos| geometry.square_at(pos));
+
+        if let Some((from, to)) = self.pending_promotion {
+            Self::draw_promotion_picker(painter, &mut self.pieces, &geometry, to, current_player);
+            let cancel = ui.ctx().input(|i| i.key_pressed(egui::Key::Escape));
+            if pressed || cancel {
+                self.pending_promotion = None;
+                let choice = Self::promotion_squares(to)
+                    .zip(PROMOTION_CHOICES)
+                    .find(|(square, _)| Some(*square) == pointer_square && !cancel);
+                // Anywhere else cancels and leaves the pawn where it was.
+                if let Some((_, piece_type)) = choice {
+                    self.play_human_move(ChessMove::with_promotion(from, to, piece_type));
+                }
+            }
+            ui.ctx().request_repaint();
+            return;
+        }
+
+        if pressed && let (Some(square), Some(pos)) = (pointer_square, pointer) {
+            let own_piece = board[square.row()][square.col()]
+                .is_some_and(|piece| piece.color == current_player);
+            match self.selected_position {
+                // Second click of a click-to-move on a target square.
+                Some(selected) if selected != square && !own_piece => {
+                    self.handle_move(selected, square);
+                    self.selected_position = None;
+                }
+                _ if own_piece => {
+                    self.deselect_on_release = self.selected_position == Some(square);
+                    self.selected_position = Some(square);
+                    self.dragging_piece = Some((square, pos));
+                }
+                _ => self.selected_position = None,
+            }
+        }
+
+        if released && let Some((from, _)) = self.dragging_piece.take() {
+            match pointer_square {
+                // A click on the piece: keep it selected for a click on the
+                // target, or drop the selection if it was already selected.
+                Some(square) if square == from => {
+                    if self.deselect_on_release {
+                        self.selected_position = None;
+                    }
+                }
+                Some(square) => {
+                    self.handle_move(from, square);
+                    self.selected_position = None;
+                    // The piece is already where it was dropped.
+                    self.shown = Some(self.displayed_node());
+                }
+                None => self.selected_position = None,
+            }
+        }
+
+        if ui.ctx().input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.selected_position = None;
+            self.dragging_piece = None;
+        }
+
+        if let Some((from, _)) = self.dragging_piece
+            && let Some(mouse_pos) = ui.ctx().pointer_hover_pos()
+            && is_dragging
+        {
+            self.dragging_piece = Some((from, mouse_pos));
+            if let Some(piece) = board[from.row()][from.col()] {
+                self.pieces
+                    .draw(painter, piece, mouse_pos, square_size * 1.15);
+            }
+        }
+
+        if self.dragging_piece.is_some() || self.selected_position.is_some() {
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn king_square(position: &Position, color: PieceColor) -> Option<Square> {
+        (0..64u8)
+            .filter_map(|index| Square::try_from(index).ok())
+            .find(|&square| position.piece_at(square) == Some(Piece::new(PieceType::King, color)))
+    }
+
+    fn current_node(&self) -> NodeId {
+        *self.line.last().expect("the line starts at the root")
+    }
+
+    fn flag_fell_at(&self, node: NodeId) -> Option<Outcome> {
+        self.clock_outcome
+            .filter(|(flagged_at, _)| *flagged_at == node)
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// How the game ended, on the board or on the clock.
+    fn outcome(&self) -> Option<Outcome> {
+        self.flag_fell_at(self.current_node())
+            .or_else(|| self.game.outcome())
+    }
+
+    /// Same as `outcome` for the position on the board.
+    fn displayed_outcome(&self) -> Option<Outcome> {
+        if self.view_ply.is_none() {
+            return self.outcome();
+        }
+        let node = self.displayed_node();
+        self.flag_fell_at(node)
+            .or_else(|| self.tree.game_at(node).outcome())
+    }
+
+    fn clock_running(&self) -> bool {
+        self.clock.as_ref().is_some_and(|clock| clock.is_running())
+    }
+
+    fn describe_outcome(outcome: &Outcome) -> String {
+        let result = match outcome.result {
+            GameResult::WhiteWins => "White wins",
+            GameResult::BlackWins => "Black wins",
+            GameResult::Draw => "Draw",
+        };
+        format!("{} by {}", result, outcome.termination)
+    }
+
+    /// Starts over from the initial position with the current setup.
+    fn new_game(&mut self) {
+        self.new_game_from(Position::default());
+    }
+
+    fn new_game_from(&mut self, start: Position) {
+        self.reset_interaction();
+        self.game = Game::from_position(start);
+        self.tree = GameTree::new(start);
+        self.line = vec![GameTree::ROOT];
+        self.redo_stack.clear();
+        self.markup.clear();
+        self.dismissed_game_over = None;
+        // Node ids of the old tree mean nothing in the new one.
+        self.shown = None;
+        self.set_time_control(self.setup.clock.clone());
+        // A fresh engine also forgets what it learned in the last game.
+        self.computer = None;
+        if self.setup.has_computer() {
+            match ComputerOpponent::new(&self.setup) {
+                Ok(computer) => self.computer = Some(computer),
+                Err(e) => self.error = Some(format!("The computer cannot play: {}", e)),
+            }
+        }
+    }
+
+    fn start_game(&mut self, setup: GameSetup) {
+        if self.auto_orient
+            && let Some(human) = setup.human_color()
+        {
+            self.flipped = human == PieceColor::Black;
+        }
+        self.setup = setup;
+        self.new_game();
+    }
+
+    fn is_computer_turn(&self) -> bool {
+        self.computer.is_some()
+            && self
+                .setup
+                .side(self.game.position().side_to_move())
+                .is_computer()
+    }
+
+    /// Plays the computer's move once it is ready, and sets the computer
+    /// thinking when its turn comes.
+    fn drive_computer(&mut self, ctx: &egui::Context) {
+        let finished = self.outcome().is_some();
+        let paused = self.clock.as_ref().is_some_and(|clock| clock.is_paused());
+        let computer_turn = self.is_computer_turn();
+        let Some(computer) = &mut self.computer else {
+            return;
+        };
+        if finished {
+            computer.cancel();
+            return;
+        }
+        match computer.poll(self.game.ply_count()) {
+            Some(Ok(mv)) => {
+                self.play_move(mv);
+                return;
+            }
+            // A crashed engine or one playing illegal moves is given up on;
+            // its side is then left to the user.
+            Some(Err(e)) => {
+                self.error = Some(format!("The computer cannot play: {}", e));
+                self.computer = None;
+                return;
+            }
+            None => {}
+        }
+        if computer_turn && !paused && !computer.is_thinking() {
+            let request = match &self.clock {
+                Some(clock) => MoveRequest {
+                    limits: SearchLimits::default(),
+                    clock: Some(clock.times(Instant::now())),
+                },
+                None => MoveRequest {
+                    limits: SearchLimits {
+                        movetime: Some(self.setup.move_time),
+                        ..SearchLimits::default()
+                    },
+                    clock: None,
+                },
+            };
+            computer.start(&self.game, request, ctx);
+        }
+    }
+
+    fn game_pgn(&self) -> String {
+        let mut tree = self.tree.clone();
+        for (&node, markup) in &self.markup {
+            let comment = format!("{} {}", markup, tree.comment(node));
+            tree.set_comment(node, comment.trim());
+        }
+        // The result is that of the main line, whichever line is shown.
+        let end = *tree
+            .mainline()
+            .last()
+            .expect("the root is on the main line");
+        let outcome = self
+            .flag_fell_at(end)
+            .or_else(|| tree.game_at(end).outcome());
+        let mut pgn = PgnGame::from_tree(tree);
+        pgn.set_tag("Event", "Casual game");
+        pgn.set_result(outcome.map(|outcome| outcome.result));
+        if let Some(outcome) = outcome {
+            let termination = match outcome.termination {
+                Termination::Timeout => "time forfeit",
+                _ => "normal",
+            };
+            pgn.set_tag("Termination", termination);
+        }
+        pgn.to_string()
+    }
+
+    fn saved_state(&self) -> SavedState {
+        SavedState {
+            board_theme: self.board_theme.name.to_string(),
+            piece_set: self.pieces.set,
+            flipped: self.flipped,
+            auto_orient: self.auto_orient,
+            show_coordinates: self.show_coordinates,
+            animate_moves: self.animate_moves,
+            animation_time: self.animation_time,
+            white: self.setup.white,
+            black: self.setup.black,
+            level: self.setup.level,
+            engine_path: self.setup.engine_path.clone(),
+            clock: self.setup.clock.as_ref().and_then(persistence::preset_name),
+            move_time_ms: self.setup.move_time.as_millis() as u64,
+            game: Some(self.game_pgn()),
+        }
+    }
+
+    /// Applies the preferences saved by an earlier run and resumes its game.
+    fn restore(&mut self, state: SavedState) {
+        if let Some(theme) = BOARD_THEMES
+            .iter()
+            .find(|theme| theme.name == state.board_theme)
+        {
+            self.board_theme = *theme;
+        }
+        self.pieces = PieceRenderer::new(state.piece_set);
+        self.flipped = state.flipped;
+        self.auto_orient = state.auto_orient;
+        self.show_coordinates = state.show_coordinates;
+        self.animate_moves = state.animate_moves;
+        self.animation_time = state.animation_time.clamp(0.05, 1.0);
+        self.setup = GameSetup {
+            white: state.white,
+            black: state.black,
+            level: state.level,
+            engine_path: state.engine_path,
+            clock: state.clock.as_deref().and_then(persistence::preset_named),
+            move_time: Duration::from_millis(state.move_time_ms),
+        };
+        self.new_game_dialog = NewGameDialog::new(&self.setup);
+
+        let saved = state
+            .game
+            .as_deref()
+            .and_then(|text| parse_pgn(text).ok())
+            .and_then(|games| games.into_iter().next());
+        match saved {
+            Some(pgn) => self.load_game(pgn),
+            None => self.new_game(),
+        }
+    }
+
+    /// Continues the main line of `pgn` with the current setup, taking the
+    /// drawings out of its comments.
+    fn load_game(&mut self, pgn: PgnGame) {
+        self.new_game_from(*pgn.tree.start_position());
+        self.tree = pgn.tree.clone();
+        for node in self.tree.nodes() {
+            let (markup, rest) = Markup::from_comment(self.tree.comment(node));
+            self.tree.set_comment(node, rest);
+            if !markup.is_empty() {
+                self.markup.insert(node, markup);
+            }
+        }
+        let end = *self
+            .tree
+            .mainline()
+            .last()
+            .expect("the root is on the main line");
+        self.line = self.tree.path(end);
+        self.game = self.tree.game_at(end);
+        // The clocks start again from the full time; only a flag that had
+        // already fallen is kept.
+        self.set_time_control(self.setup.clock.clone());
+        if pgn.tag("Termination") == Some("time forfeit")
+            && let Some(result) = pgn.result()
+        {
+            let outcome = Outcome {
+                result,
+                termination: Termination::Timeout,
+            };
+            self.clock_outcome = Some((end, outcome));
+        }
+        if self.outcome().is_some()
+            && let Some(clock) = &mut self.clock
+        {
+            clock.pause(Instant::now());
+        }
+    }
+
+    /// Loads a FEN position or PGN from `text`, asking which game to load
+    /// when there are several.
+    fn load_text(&mut self, text: &str) {
+        match files::import_text(text) {
+            Ok(Imported::Position(position)) => self.new_game_from(position),
+            Ok(Imported::Games(games)) => self.load_games(games),
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    fn load_games(&mut self, mut games: Vec<PgnGame>) {
+        if games.len() == 1 {
+            self.load_game(games.remove(0));
+        } else {
+            self.game_picker = Some(GamePicker::new(games));
+        }
+    }
+
+    fn open_pgn_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PGN", &["pgn"])
+            .pick_file()
+        else {
+            return;
+        };
+        match files::read_pgn_file(&path) {
+            Ok(games) => self.load_games(games),
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    fn save_pgn_file(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PGN", &["pgn"])
+            .set_file_name("game.pgn")
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(message) = files::write_pgn_file(&path, &self.game_pgn()) {
+            self.error = Some(message);
+        }
+    }
+
+    fn paste_from_clipboard(&mut self) {
+        match files::read_clipboard() {
+            Ok(text) => self.load_text(&text),
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    fn draw_file_menu(&mut self, ui: &mut egui::Ui) {
+        if ui.button("Open PGN…").clicked() {
+            ui.close_menu();
+            self.open_pgn_file();
+        }
+        if ui.button("Save PGN…").clicked() {
+            ui.close_menu();
+            self.save_pgn_file();
+        }
+        ui.separator();
+        if ui
+            .button("Paste FEN/PGN")
+            .on_hover_text("Load a position or game from the clipboard")
+            .clicked()
+        {
+            ui.close_menu();
+            self.paste_from_clipboard();
+        }
+        if ui
+            .button("Copy FEN")
+            .on_hover_text("Copy the position on the board")
+            .clicked()
+        {
+            let fen = self.displayed_position().to_fen();
+            ui.output_mut(|output| output.copied_text = fen);
+            ui.close_menu();
+        }
+        if ui.button("Copy PGN").clicked() {
+            let pgn = self.game_pgn();
+            ui.output_mut(|output| output.copied_text = pgn);
+            ui.close_menu();
+        }
+    }
+
+    fn draw_error(&mut self, ctx: &egui::Context) {
+        let Some(message) = &self.error else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("Error")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(message);
+                ui.add_space(8.0);
+                close = ui.button("OK").clicked();
+            });
+        if close {
+            self.error = None;
+        }
+    }
+
+    /// Result dialog over the board once the game has ended, until closed.
+    fn draw_game_over(&mut self, ctx: &egui::Context) {
+        let Some(outcome) = self.outcome() else {
+            return;
+        };
+        if self.dismissed_game_over == Some(self.current_node()) {
+            return;
+        }
+        let screen = ctx.screen_rect();
+        ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Middle,
+            egui::Id::new("game_over_shade"),
+        ))
+        .rect_filled(screen, 0.0, Color32::from_black_alpha(120));
+
+        let mut open = true;
+        egui::Window::new("Game over")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.heading(outcome.result.to_string());
+                    ui.label(Self::describe_outcome(&outcome));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("New game").clicked() {
+                            self.new_game();
+                        }
+                        if ui
+                            .button("Export PGN")
+                            .on_hover_text("Copy the game to the clipboard")
+                            .clicked()
+                        {
+                            let pgn = self.game_pgn();
+                            ui.output_mut(|output| output.copied_text = pgn);
+                            self.pgn_copied = true;
+                        }
+                    });
+                    if self.pgn_copied {
+                        ui.label("PGN copied to the clipboard.");
+                    }
+                });
+            });
+        if !open {
+            self.dismissed_game_over = Some(self.current_node());
+        }
+    }
+
+    fn toggle_analysis(&mut self) {
+        self.analyzer = match self.analyzer {
+            Some(_) => None,
+            None => Some(Analyzer::new()),
+        };
+    }
+
+    /// Keeps the analysis on the displayed position.
+    fn update_analysis(&mut self, ctx: &egui::Context) {
+        let ply = self.displayed_ply();
+        let position = *self.displayed_position();
+        let history = (0..ply)
+            .filter_map(|index| self.game.position_at(index))
+            .map(|position| position.hash())
+            .collect();
+        if let Some(analyzer) = &mut self.analyzer {
+            analyzer.analyze(&position, history, ctx);
+        }
+    }
+
+    fn draw_analysis(&self, ui: &mut egui::Ui) {
+        let Some(analyzer) = &self.analyzer else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.strong("Analysis");
+            match analyzer.snapshot() {
+                Some(snapshot) => {
+                    ui.label(format!(
+                        "depth {}, {} kN",
+                        snapshot.depth,
+                        snapshot.nodes / 1000
+                    ));
+                }
+                None => {
+                    ui.spinner();
+                }
+            }
+        });
+        if let Some(snapshot) = analyzer.snapshot() {
+            for line in &snapshot.lines {
+                let white_score = match snapshot.position.side_to_move() {
+                    PieceColor::White => line.score,
+                    PieceColor::Black => -line.score,
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.strong(analysis::format_score(white_score));
+                    ui.label(analysis::san_line(&snapshot.position, &line.pv));
+                });
+            }
+        }
+        ui.separator();
+    }
+
+    fn displayed_ply(&self) -> usize {
+        self.view_ply.unwrap_or(self.game.ply_count())
+    }
+
+    fn displayed_position(&self) -> &Position {
+        self.game
+            .position_at(self.displayed_ply())
+            .expect("the viewed ply is within the game")
+    }
+
+    fn displayed_node(&self) -> NodeId {
+        self.line[self.displayed_ply()]
+    }
+
+    /// Shows the position after `ply` moves, following the game again once
+    /// `ply` reaches its end.
+    fn go_to_ply(&mut self, ply: usize) {
+        self.view_ply = (ply < self.game.ply_count()).then_some(ply);
+        self.selected_position = None;
+        self.dragging_piece = None;
+    }
+
+    fn handle_navigation_keys(&mut self, ctx: &egui::Context) {
+        if ctx.memory(|memory| memory.focus().is_some()) {
+            return;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::F) && i.modifiers.is_none()) {
+            self.flipped = !self.flipped;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::A) && i.modifiers.is_none()) {
+            self.toggle_analysis();
+        }
+        let ply = self.displayed_ply();
+        let (previous, next, first, last) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowLeft),
+                i.key_pressed(egui::Key::ArrowRight),
+                i.key_pressed(egui::Key::Home) || i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::End) || i.key_pressed(egui::Key::ArrowDown),
+            )
+        });
+        if previous {
+            self.go_to_ply(ply.saturating_sub(1));
+        } else if next {
+            self.go_to_ply(ply + 1);
+        } else if first {
+            self.go_to_ply(0);
+        } else if last {
+            self.go_to_ply(self.game.ply_count());
+        }
+    }
+
+    fn draw_move_list(&mut self, ui: &mut egui::Ui) {
+        let ply_count = self.game.ply_count();
+        let displayed = self.displayed_ply();
+        ui.horizontal(|ui| {
+            let buttons = [
+                ("⏮", 0, displayed > 0),
+                ("◀", displayed.saturating_sub(1), displayed > 0),
+                ("▶", displayed + 1, displayed < ply_count),
+                ("⏭", ply_count, displayed < ply_count),
+            ];
+            for (label, ply, enabled) in buttons {
+                if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                    self.go_to_ply(ply);
+                }
+            }
+        });
+        ui.separator();
+
+        self.draw_comment_editor(ui);
+        ui.separator();
+
+        let locked_line = self.clock_running().then_some(self.line.as_slice());
+        let action = egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                MoveList::new(&self.tree, self.displayed_node(), locked_line).show(ui)
+            })
+            .inner;
+        match action {
+            Some(MoveListAction::Select(node)) => self.go_to_node(node),
+            Some(MoveListAction::Promote(node)) => self.tree.promote(node),
+            Some(MoveListAction::MakeMainline(node)) => self.tree.make_mainline(node),
+            Some(MoveListAction::Delete(node)) => self.delete_from(node),
+            Some(MoveListAction::ToggleNag(node, nag)) => self.toggle_assessment(node, nag),
+            None => {}
+        }
+    }
+
+    fn draw_comment_editor(&mut self, ui: &mut egui::Ui) {
+        let node = self.displayed_node();
+        let mut comment = self.tree.comment(node).to_string();
+        let editor = egui::TextEdit::multiline(&mut comment)
+            .hint_text("Comment on this position")
+            .desired_rows(2)
+            .desired_width(f32::INFINITY);
+        if ui.add(editor).changed() {
+            self.tree.set_comment(node, comment);
+        }
+    }
+
+    /// Shows `node`, switching to the line through it if it is not on the
+    /// current one.
+    fn go_to_node(&mut self, node: NodeId) {
+        match self.line.iter().position(|&step| step == node) {
+            Some(ply) => self.go_to_ply(ply),
+            None => self.switch_line(node),
+        }
+    }
+
+    /// Makes the line through `node`, continued along its main moves, the
+    /// current one and shows `node`. The clock is left as it is.
+    fn switch_line(&mut self, node: NodeId) {
+        if let Some(computer) = &mut self.computer {
+            computer.cancel();
+        }
+        self.reset_interaction();
+        self.redo_stack.clear();
+        let mut line = self.tree.path(node);
+        line.extend(self.tree.continuation(node).into_iter().skip(1));
+        let end = *line.last().expect("a line has the root");
+        self.game = self.tree.game_at(end);
+        self.line = line;
+        self.go_to_ply(self.tree.ply(node));
+    }
+
+    /// Removes the move leading to `node` and all that follows it, moving to
+    /// the position before it if it was on the current line.
+    fn delete_from(&mut self, node: NodeId) {
+        let Some(parent) = self.tree.parent(node) else {
+            return;
+        };
+        self.tree.delete(node);
+        if self.line.contains(&node) {
+            self.switch_line(parent);
+        }
+    }
+
+    /// Sets or clears one of the move assessments, which exclude each other.
+    fn toggle_assessment(&mut self, node: NodeId, nag: u8) {
+        let mut nags = self.tree.nags(node).to_vec();
+        let had = nags.contains(&nag);
+        nags.retain(|existing| !MOVE_ASSESSMENTS.iter().any(|(known, _)| known == existing));
+        if !had {
+            nags.push(nag);
+        }
+        self.tree.set_nags(node, nags);
+    }
+
+    /// Squares covered by the promotion picker, from the promotion square
+    /// towards the middle of the board.
+    fn promotion_squares(to: Square) -> impl Iterator<Item = Square> {
+        let toward_center: i32 = if to.row() == 0 { 1 } else { -1 };
+        (0..PROMOTION_CHOICES.len() as i32).filter_map(move |i| to.offset(i * toward_center, 0))
+    }
+
+    fn draw_promotion_picker(
+        painter: &egui::Painter,
+        pieces: &mut PieceRenderer,
+        geometry: &BoardGeometry,
+        to: Square,
+        color: PieceColor,
+    ) {
+        let square_size = geometry.square_size;
+        painter.rect_filled(geometry.rect, 0.0, Color32::from_black_alpha(110));
+        for (square, piece_type) in Self::promotion_squares(to).zip(PROMOTION_CHOICES) {
+            let rect = geometry.square_rect(square);
+            painter.rect_filled(rect, 0.0, Color32::from_gray(200));
+            painter.circle_filled(rect.center(), square_size * 0.45, Color32::from_gray(150));
+            pieces.draw(
+                painter,
+                Piece::new(piece_type, color),
+                rect.center(),
+                square_size,
+            );
+        }
+    }
+
+    /// Plays `from`-`to` if it is legal; an illegal drop just puts the
+    /// piece back. A promotion waits for the piece to be picked.
+    fn handle_move(&mut self, from: Square, to: Square) -> bool {
+        let Some(mv) = self.displayed_position().find_legal_move(from, to) else {
+            return false;
+        };
+        if mv.promotion.is_some() {
+            self.pending_promotion = Some((from, to));
+            return false;
+        }
+        self.play_human_move(mv)
+    }
+
+    /// Plays a move made on the board. From an earlier position it starts a
+    /// new line there, leaving the moves that followed as a variation.
+    fn play_human_move(&mut self, mv: ChessMove) -> bool {
+        if let Some(ply) = self.view_ply {
+            if let Some(computer) = &mut self.computer {
+                computer.cancel();
+            }
+            while self.game.ply_count() > ply {
+                self.game.undo();
+                self.line.pop();
+            }
+            self.redo_stack.clear();
+            self.view_ply = None;
+        }
+        self.play_move(mv)
+    }
+
+    /// Adds `mv` to the current line and to the tree, where a move already
+    /// played from this position is followed again.
+    fn extend_line(&mut self, mv: ChessMove) -> bool {
+        let Ok(node) = self.tree.play(self.current_node(), mv) else {
+            return false;
+        };
+        self.game.play(mv).expect("the tree accepted the move");
+        self.line.push(node);
+        true
+    }
+
+    fn play_move(&mut self, mv: ChessMove) -> bool {
+        let mover = self.game.position().side_to_move();
+        if !self.extend_line(mv) {
+            return false;
+        }
+        if let Some(clock) = &mut self.clock
+            && clock.flagged().is_none()
+        {
+            if let Some(flagged) = clock.press(mover, Instant::now()) {
+                let outcome = timeout_outcome(self.game.position(), flagged);
+                self.clock_outcome = Some((self.current_node(), outcome));
+            } else if self.game.outcome().is_some() {
+                clock.pause(Instant::now());
+            }
+        }
+        self.pgn_copied = false;
+        // Replaying the undone move keeps the rest of the redo history.
+        if self.redo_stack.last() == Some(&mv) {
+            self.redo_stack.pop();
+        } else {
+            self.redo_stack.clear();
+        }
+        true
+    }
+
+    /// Clears input state tied to the current position before the game
+    /// changes under it.
+    fn reset_interaction(&mut self) {
+        self.selected_position = None;
+        self.dragging_piece = None;
+        self.pending_promotion = None;
+        self.view_ply = None;
+    }
+
+    /// Takes back one move, or in a game against the computer everything
+    /// back to the human's last turn.
+    fn undo(&mut self) {
+        if let Some(computer) = &mut self.computer {
+            computer.cancel();
+        }
+        self.undo_ply();
+        while self.setup.human_color().is_some()
+            && self.is_computer_turn()
+            && self.game.ply_count() > 0
+        {
+            self.undo_ply();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(computer) = &mut self.computer {
+            computer.cancel();
+        }
+        self.redo_ply();
+        while self.setup.human_color().is_some()
+            && self.is_computer_turn()
+            && !self.redo_stack.is_empty()
+        {
+            self.redo_ply();
+        }
+    }
+
+    fn undo_ply(&mut self) {
+        self.reset_interaction();
+        if let Some(mv) = self.game.undo() {
+            self.line.pop();
+            self.redo_stack.push(mv);
+            self.sync_clock_turn();
+        }
+    }
+
+    fn redo_ply(&mut self) {
+        self.reset_interaction();
+        if let Some(mv) = self.redo_stack.pop() {
+            if !self.extend_line(mv) {
+                self.redo_stack.clear();
+            }
+            self.sync_clock_turn();
+        }
+    }
+
+    /// Hands the running clock to the side to move after the game was
+    /// changed without a move being played, as with undo and redo.
+    fn sync_clock_turn(&mut self) {
+        let side = self.game.position().side_to_move();
+        if let Some(clock) = &mut self.clock
+            && clock.active().is_some()
+            && clock.active() != Some(side)
+        {
+            clock.switch_to(side, Instant::now());
+            // The clock stopped at the end of the game; play goes on now.
+            if self.game.outcome().is_none() {
+                clock.resume(Instant::now());
+            }
+        }
+    }
+
+    fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.clock_outcome = None;
+        self.setup.clock = control.clone();
+        self.clock = control.map(|control| {
+            let mut clock = ChessClock::new(control);
+            // Mid-game the clock starts at once; otherwise with the first move.
+            if self.game.ply_count() > 0 {
+                clock.switch_to(self.game.position().side_to_move(), Instant::now());
+            }
+            clock
+        });
+    }
+
+    /// Records a flag fall on the running clock.
+    fn update_clock(&mut self, ctx: &egui::Context) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        let flagged = clock.check_flag(Instant::now());
+        if clock.is_running() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        if self.clock_outcome.is_none()
+            && let Some(flagged) = flagged
+        {
+            let outcome = timeout_outcome(self.game.position(), flagged);
+            self.clock_outcome = Some((self.current_node(), outcome));
+            self.reset_interaction();
+        }
+    }
+
+    fn draw_clocks(&mut self, ui: &mut egui::Ui) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        let now = Instant::now();
+        let bottom = if self.flipped {
+            PieceColor::Black
+        } else {
+            PieceColor::White
+        };
+        clock_view::draw_clock(ui, clock, bottom.opposite(), now);
+        clock_view::draw_clock(ui, clock, bottom, now);
+        ui.horizontal(|ui| {
+            ui.label(clock.control().to_string());
+            if clock.is_paused() {
+                if ui.button("▶ Resume").clicked() {
+                    clock.resume(now);
+                }
+            } else if clock.is_running() && ui.button("⏸ Pause").clicked() {
+                clock.pause(now);
+            }
+        });
+        ui.separator();
+    }
+
+    /// Takes back the last move of each side, so the player who moved
+    /// before the reply is to move again.
+    fn take_back(&mut self) {
+        if self.setup.human_color().is_some() {
+            self.undo();
+            return;
+        }
+        let plies = self.game.ply_count().min(2);
+        for _ in 0..plies {
+            self.undo_ply();
+        }
+    }
+
+    fn draw_toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.menu_button("File", |ui| self.draw_file_menu(ui));
+            if ui.button("New game…").clicked() {
+                self.new_game_dialog.open = true;
+            }
+            if ui.button("Edit position").clicked() {
+                self.editor = Some(PositionEditor::new(self.displayed_position()));
+            }
+            ui.separator();
+            let can_undo = self.game.ply_count() > 0;
+            if ui
+                .add_enabled(can_undo, egui::Button::new("↶ Undo"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(!self.redo_stack.is_empty(), egui::Button::new("↷ Redo"))
+                .on_hover_text("Ctrl+Y")
+                .clicked()
+            {
+                self.redo();
+            }
+            if ui
+                .add_enabled(can_undo, egui::Button::new("Take back"))
+                .on_hover_text("Take back your move and the reply")
+                .clicked()
+            {
+                self.take_back();
+            }
+            ui.separator();
+            if ui.button("⟲ Flip board").on_hover_text("F").clicked() {
+                self.flipped = !self.flipped;
+            }
+            let analysing = self.analyzer.is_some();
+            if ui
+                .selectable_label(analysing, "Analysis")
+                .on_hover_text("A")
+                .clicked()
+            {
+                self.toggle_analysis();
+            }
+            ui.menu_button("Clock", |ui| {
+                if ui.button("No clock").clicked() {
+                    self.set_time_control(None);
+                    ui.close_menu();
+                }
+                for (name, control) in clock_view::presets() {
+                    if ui.button(name).clicked() {
+                        self.set_time_control(Some(control));
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui
+                .selectable_label(self.show_settings, "⚙ Settings")
+                .clicked()
+            {
+                self.show_settings = !self.show_settings;
+            }
+            ui.menu_button("View", |ui| {
+                ui.checkbox(&mut self.show_coordinates, "Coordinates");
+                ui.checkbox(&mut self.auto_orient, "Face the human player");
+            });
+            if let Some(computer) = &self.computer
+                && computer.is_thinking()
+            {
+                ui.separator();
+                ui.spinner();
+                ui.label("Thinking…");
+                if ui
+                    .button("Force move")
+                    .on_hover_text("Play the best move found so far")
+                    .clicked()
+                {
+                    computer.force_move();
+                }
+            }
+        });
+    }
+
+    fn draw_settings(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.strong("Pieces");
+                ui.horizontal_wrapped(|ui| {
+                    for set in PieceSet::ALL {
+                        ui.selectable_value(&mut self.pieces.set, set, set.name());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let samples = [PieceType::King, PieceType::Queen, PieceType::Knight]
+                        .into_iter()
+                        .flat_map(|piece_type| {
+                            [PieceColor::White, PieceColor::Black]
+                                .map(|color| Piece::new(piece_type, color))
+                        });
+                    for (index, piece) in samples.enumerate() {
+                        let (rect, _) =
+                            ui.allocate_exact_size(Vec2::splat(36.0), egui::Sense::hover());
+                        let fill = [self.board_theme.light, self.board_theme.dark][index % 2];
+                        ui.painter().rect_filled(rect, 0.0, fill);
+                        self.pieces.draw(ui.painter(), piece, rect.center(), 36.0);
+                    }
+                });
+                ui.separator();
+                ui.strong("Board");
+                for theme in BOARD_THEMES {
+                    ui.horizontal(|ui| {
+                        for color in [theme.light, theme.dark] {
+                            let (rect, _) =
+                                ui.allocate_exact_size(Vec2::splat(16.0), egui::Sense::hover());
+                            ui.painter().rect_filled(rect, 2.0, color);
+                        }
+                        ui.selectable_value(&mut self.board_theme, theme, theme.name);
+                    });
+                }
+                ui.separator();
+                ui.checkbox(&mut self.show_coordinates, "Coordinates");
+                ui.checkbox(&mut self.animate_moves, "Animate moves");
+                ui.add_enabled(
+                    self.animate_moves,
+                    egui::Slider::new(&mut self.animation_time, 0.05..=1.0)
+                        .text("Animation time")
+                        .suffix(" s"),
+                );
+            });
+        self.show_settings = open;
+    }
+
+    fn handle_edit_keys(&mut self, ctx: &egui::Context) {
+        // Ctrl+Z in a text field, e.g. while editing a comment, belongs to it.
+        if ctx.memory(|memory| memory.focus().is_some()) {
+            return;
+        }
+        let (undo, redo) = ctx.input(|i| {
+            let command = i.modifiers.command;
+            let z = i.key_pressed(egui::Key::Z);
+            (
+                command && z && !i.modifiers.shift,
+                command && (i.key_pressed(egui::Key::Y) || (z && i.modifiers.shift)),
+            )
+        });
+        if undo {
+            self.undo();
+        } else if redo {
+            self.redo();
+        }
+    }
+}
+
+impl eframe::App for ChessUi {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, persistence::STORAGE_KEY, &self.saved_state());
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(editor) = &mut self.editor {
+            let mut action = None;
+            egui::SidePanel::right("editor")
+                .resizable(true)
+                .default_width(260.0)
+                .show(ctx, |ui| {
+                    action = editor.draw_controls(ui, &mut self.pieces);
+                });
+            egui::CentralPanel::default().show(ctx, |ui| {
+                editor.draw_board(ui, self.flipped, &mut self.pieces, &self.board_theme);
+            });
+            match action {
+                Some(EditorAction::Play(start)) => {
+                    self.editor = None;
+                    self.new_game_from(start);
+                }
+                Some(EditorAction::Cancel) => self.editor = None,
+                None => {}
+            }
+            return;
+        }
+        self.handle_navigation_keys(ctx);
+        self.handle_edit_keys(ctx);
+        self.update_clock(ctx);
+        self.drive_computer(ctx);
+        self.update_analysis(ctx);
+        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            self.draw_toolbar(ui);
+        });
+        egui::SidePanel::right("move_list")
+            .resizable(true)
+            .default_width(180.0)
+            .show(ctx, |ui| {
+                self.draw_clocks(ui);
+                self.draw_analysis(ui);
+                self.draw_move_list(ui);
+            });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.draw_board(ui);
+        });
+        self.draw_game_over(ctx);
+        self.draw_settings(ctx);
+        if let Some(setup) = self.new_game_dialog.show(ctx) {
+            self.start_game(setup);
+        }
+        if let Some(picker) = &mut self.game_picker {
+            match picker.show(ctx) {
+                Some(PickerAction::Load(pgn)) => {
+                    self.game_picker = None;
+                    self.load_game(pgn);
+                }
+                Some(PickerAction::Cancel) => self.game_picker = None,
+                None => {}
+            }
+        }
+        self.draw_error(ctx);
+    }
+}
+
+fn main() {
+    let options = eframe::NativeOptions {
+        initial_window_size: Some(egui::vec2(800.0, 600.0)),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Chess Game",
+        options,
+        Box::new(|cc| {
+            let mut app = ChessUi::new();
+            if let Some(state) = cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, persistence::STORAGE_KEY))
+            {
+                app.restore(state);
+            }
+            Box::new(app)
+        }),
+    )
+    .unwrap();
+}
@@ -0,0 +1,166 @@
+use chess::engine::{piece::PieceColor, san::to_san};
+use chess::pgn::nag_symbol;
+use chess::tree::{GameTree, NodeId};
+use egui::RichText;
+
+/// Move assessments offered in the context menu of a move, by NAG number.
+/// A move carries at most one of them.
+pub const MOVE_ASSESSMENTS: [(u8, &str); 6] = [
+    (3, "Brilliant move"),
+    (1, "Good move"),
+    (5, "Interesting move"),
+    (6, "Dubious move"),
+    (2, "Mistake"),
+    (4, "Blunder"),
+];
+
+pub enum MoveListAction {
+    Select(NodeId),
+    Promote(NodeId),
+    MakeMainline(NodeId),
+    Delete(NodeId),
+    ToggleNag(NodeId, u8),
+}
+
+/// Moves of the game tree laid out as text: the main line in wrapped rows,
+/// with each group of alternatives indented below the move they replace.
+pub struct MoveList<'a> {
+    tree: &'a GameTree,
+    displayed: NodeId,
+    // Nodes that cannot be left or deleted, as while the clock runs.
+    locked_line: Option<&'a [NodeId]>,
+    action: Option<MoveListAction>,
+}
+
+impl<'a> MoveList<'a> {
+    pub fn new(tree: &'a GameTree, displayed: NodeId, locked_line: Option<&'a [NodeId]>) -> Self {
+        Self {
+            tree,
+            displayed,
+            locked_line,
+            action: None,
+        }
+    }
+
+    pub fn show(mut self, ui: &mut egui::Ui) -> Option<MoveListAction> {
+        Self::draw_comment(ui, self.tree.comment(GameTree::ROOT));
+        self.draw_line(ui, GameTree::ROOT, None, 0);
+        self.action
+    }
+
+    /// Draws `first`, if given, then the moves following `from` along its
+    /// main continuation.
+    fn draw_line(&mut self, ui: &mut egui::Ui, from: NodeId, first: Option<NodeId>, depth: usize) {
+        let tree = self.tree;
+        let mut current = from;
+        let mut pending_first = first;
+        let mut numbered = true;
+        loop {
+            let mut alternatives: &[NodeId] = &[];
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 2.0;
+                if let Some(node) = pending_first.take() {
+                    self.draw_move(ui, node, true, depth);
+                    numbered = !tree.comment(node).is_empty();
+                    current = node;
+                }
+                while let Some((&main, variations)) = tree.children(current).split_first() {
+                    self.draw_move(ui, main, numbered, depth);
+                    numbered = !tree.comment(main).is_empty();
+                    current = main;
+                    if !variations.is_empty() {
+                        alternatives = variations;
+                        break;
+                    }
+                }
+            });
+            if alternatives.is_empty() {
+                break;
+            }
+            ui.indent(("variations", current), |ui| {
+                let parent = tree.parent(current).expect("alternatives have a parent");
+                for &variation in alternatives {
+                    self.draw_line(ui, parent, Some(variation), depth + 1);
+                }
+            });
+            numbered = true;
+        }
+    }
+
+    fn draw_move(&mut self, ui: &mut egui::Ui, node: NodeId, numbered: bool, depth: usize) {
+        let parent = self.tree.parent(node).expect("a move has a parent");
+        let mv = self.tree.mv(node).expect("a move has a parent");
+        let position = self.tree.position(parent);
+        let number = position.fullmove_number();
+        let mut text = match position.side_to_move() {
+            PieceColor::White => format!("{}. ", number),
+            PieceColor::Black if numbered => format!("{}… ", number),
+            PieceColor::Black => String::new(),
+        };
+        text.push_str(&to_san(position, &mv));
+        for &nag in self.tree.nags(node) {
+            match nag_symbol(nag) {
+                Some(symbol) => text.push_str(symbol),
+                None => text.push_str(&format!(" ${}", nag)),
+            }
+        }
+        let mut label = RichText::new(text);
+        if depth > 0 {
+            label = label.italics();
+        }
+        let response = ui.selectable_label(node == self.displayed, label);
+        if response.clicked() && self.can_leave_line(node) {
+            self.action = Some(MoveListAction::Select(node));
+        }
+        response.context_menu(|ui| self.draw_context_menu(ui, node));
+        Self::draw_comment(ui, self.tree.comment(node));
+    }
+
+    fn draw_context_menu(&mut self, ui: &mut egui::Ui, node: NodeId) {
+        let is_mainline = self.tree.is_mainline(node);
+        let mut choose = |ui: &mut egui::Ui, action| {
+            self.action = Some(action);
+            ui.close_menu();
+        };
+        if ui
+            .add_enabled(!is_mainline, egui::Button::new("Promote variation"))
+            .clicked()
+        {
+            choose(ui, MoveListAction::Promote(node));
+        }
+        if ui
+            .add_enabled(!is_mainline, egui::Button::new("Make main line"))
+            .clicked()
+        {
+            choose(ui, MoveListAction::MakeMainline(node));
+        }
+        let deletable = self.locked_line.is_none_or(|line| !line.contains(&node));
+        if ui
+            .add_enabled(deletable, egui::Button::new("Delete from here"))
+            .clicked()
+        {
+            choose(ui, MoveListAction::Delete(node));
+        }
+        ui.separator();
+        let nags = self.tree.nags(node);
+        for (nag, name) in MOVE_ASSESSMENTS {
+            let symbol = nag_symbol(nag).expect("assessments have symbols");
+            if ui
+                .selectable_label(nags.contains(&nag), format!("{}  {}", symbol, name))
+                .clicked()
+            {
+                choose(ui, MoveListAction::ToggleNag(node, nag));
+            }
+        }
+    }
+
+    fn can_leave_line(&self, node: NodeId) -> bool {
+        self.locked_line.is_none_or(|line| line.contains(&node))
+    }
+
+    fn draw_comment(ui: &mut egui::Ui, comment: &str) {
+        if !comment.is_empty() {
+            ui.label(RichText::new(comment).weak().italics());
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::clock_view;
+use chess::clock::TimeControl;
+use chess::engine::{piece::PieceColor, skill::MAX_SKILL_LEVEL};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Human,
+    Computer,
+    /// The UCI engine at `GameSetup::engine_path`.
+    External,
+}
+
+impl Side {
+    /// Whether a program picks the moves of this side, built in or not.
+    pub fn is_computer(self) -> bool {
+        self != Side::Human
+    }
+}
+
+/// Who plays which colour and how, as chosen in the new-game dialog.
+#[derive(Clone, Debug)]
+pub struct GameSetup {
+    pub white: Side,
+    pub black: Side,
+    pub level: u8,
+    /// Program run for an `External` side.
+    pub engine_path: String,
+    pub clock: Option<TimeControl>,
+    /// Thinking time of the computer per move when there is no clock.
+    pub move_time: Duration,
+}
+
+impl GameSetup {
+    pub fn side(&self, color: PieceColor) -> Side {
+        match color {
+            PieceColor::White => self.white,
+            PieceColor::Black => self.black,
+        }
+    }
+
+    pub fn has_computer(&self) -> bool {
+        self.white.is_computer() || self.black.is_computer()
+    }
+
+    /// The colour of the only human player, if there is exactly one.
+    pub fn human_color(&self) -> Option<PieceColor> {
+        match (self.white, self.black) {
+            (Side::Human, black) if black.is_computer() => Some(PieceColor::White),
+            (white, Side::Human) if white.is_computer() => Some(PieceColor::Black),
+            _ => None,
+        }
+    }
+}
+
+impl Default for GameSetup {
+    /// Two humans sharing the board, without a clock.
+    fn default() -> Self {
+        Self {
+            white: Side::Human,
+            black: Side::Human,
+            level: MAX_SKILL_LEVEL,
+            engine_path: String::new(),
+            clock: None,
+            move_time: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Window asking how the next game is played. It keeps the last choices so
+/// that reopening it starts from them.
+pub struct NewGameDialog {
+    pub open: bool,
+    white: Side,
+    black: Side,
+    level: u8,
+    engine_path: String,
+    // Index into `clock_view::presets`; `None` plays without a clock.
+    clock_preset: Option<usize>,
+    move_time_secs: f32,
+}
+
+impl NewGameDialog {
+    /// A closed dialog whose choices start from `setup`.
+    pub fn new(setup: &GameSetup) -> Self {
+        let clock_preset = setup.clock.as_ref().and_then(|control| {
+            clock_view::presets()
+                .iter()
+                .position(|(_, preset)| preset == control)
+        });
+        Self {
+            open: false,
+            white: setup.white,
+            black: setup.black,
+            level: setup.level,
+            engine_path: setup.engine_path.clone(),
+            clock_preset,
+            move_time_secs: setup.move_time.as_secs_f32(),
+        }
+    }
+
+    /// Draws the dialog while it is open, returning the setup once the user
+    /// starts the game.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<GameSetup> {
+        if !self.open {
+            return None;
+        }
+        let presets = clock_view::presets();
+        let mut start = false;
+        let mut cancel = false;
+        egui::Window::new("New game")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Grid::new("new_game")
+                    .num_columns(2)
+                    .spacing([12.0, 8.0])
+                    .show(ui, |ui| {
+                        for (label, side) in
+                            [("White", &mut self.white), ("Black", &mut self.black)]
+                        {
+                            ui.label(label);
+                            ui.horizontal(|ui| {
+                                ui.selectable_value(side, Side::Human, "Human");
+                                ui.selectable_value(side, Side::Computer, "Computer");
+                                ui.selectable_value(side, Side::External, "External engine");
+                            });
+                            ui.end_row();
+                        }
+
+                        let sides = [self.white, self.black];
+                        let has_computer = sides.iter().any(|side| side.is_computer());
+                        ui.label("Engine level");
+                        ui.add_enabled(
+                            sides.contains(&Side::Computer),
+                            egui::Slider::new(&mut self.level, 0..=MAX_SKILL_LEVEL),
+                        );
+                        ui.end_row();
+
+                        if sides.contains(&Side::External) {
+                            ui.label("Engine path");
+                            ui.horizontal(|ui| {
+                                ui.text_edit_singleline(&mut self.engine_path)
+                                    .on_hover_text("A chess engine speaking the UCI protocol");
+                                if ui.button("Browse…").clicked()
+                                    && let Some(path) = rfd::FileDialog::new().pick_file()
+                                {
+                                    self.engine_path = path.display().to_string();
+                                }
+                            });
+                            ui.end_row();
+                        }
+
+                        ui.label("Clock");
+                        let selected = self
+                            .clock_preset
+                            .and_then(|index| presets.get(index))
+                            .map_or("No clock", |(name, _)| name);
+                        egui::ComboBox::from_id_source("new_game_clock")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.clock_preset, None, "No clock");
+                                for (index, (name, _)) in presets.iter().enumerate() {
+                                    ui.selectable_value(&mut self.clock_preset, Some(index), *name);
+                                }
+                            });
+                        ui.end_row();
+
+                        if self.clock_preset.is_none() {
+                            ui.label("Computer time per move");
+                            ui.add_enabled(
+                                has_computer,
+                                egui::Slider::new(&mut self.move_time_secs, 0.1..=30.0)
+                                    .logarithmic(true)
+                                    .suffix(" s"),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                ui.add_space(8.0);
+                // An external engine cannot start without a program to run.
+                let ready = !self.engine_path.trim().is_empty()
+                    || ![self.white, self.black].contains(&Side::External);
+                ui.horizontal(|ui| {
+                    start = ui.add_enabled(ready, egui::Button::new("Start")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if cancel {
+            self.open = false;
+        }
+        if !start {
+            return None;
+        }
+        self.open = false;
+        Some(GameSetup {
+            white: self.white,
+            black: self.black,
+            level: self.level,
+            engine_path: self.engine_path.trim().to_string(),
+            clock: self
+                .clock_preset
+                .and_then(|index| presets.into_iter().nth(index))
+                .map(|(_, control)| control),
+            move_time: Duration::from_secs_f32(self.move_time_secs),
+        })
+    }
+}
@@ -0,0 +1,57 @@
+use crate::clock_view;
+use crate::new_game::Side;
+use crate::theme::PieceSet;
+use chess::clock::TimeControl;
+use serde::{Deserialize, Serialize};
+
+/// Key of the saved state in eframe's storage.
+pub const STORAGE_KEY: &str = "chess_gui";
+
+/// Preferences and the game in progress, written to eframe's storage on
+/// exit and read back on the next launch.
+#[derive(Serialize, Deserialize)]
+pub struct SavedState {
+    pub board_theme: String,
+    pub piece_set: PieceSet,
+    pub flipped: bool,
+    pub auto_orient: bool,
+    pub show_coordinates: bool,
+    // Missing from states saved before moves were animated.
+    #[serde(default = "default_animate_moves")]
+    pub animate_moves: bool,
+    #[serde(default = "default_animation_time")]
+    pub animation_time: f32,
+    pub white: Side,
+    pub black: Side,
+    pub level: u8,
+    #[serde(default)]
+    pub engine_path: String,
+    /// Name of the clock preset, or `None` without a clock.
+    pub clock: Option<String>,
+    pub move_time_ms: u64,
+    /// The game as PGN, with the markup in its comments.
+    pub game: Option<String>,
+}
+
+fn default_animate_moves() -> bool {
+    true
+}
+
+fn default_animation_time() -> f32 {
+    0.2
+}
+
+/// Name under which `control` appears in `clock_view::presets`.
+pub fn preset_name(control: &TimeControl) -> Option<String> {
+    clock_view::presets()
+        .into_iter()
+        .find(|(_, preset)| preset == control)
+        .map(|(name, _)| name.to_string())
+}
+
+pub fn preset_named(name: &str) -> Option<TimeControl> {
+    clock_view::presets()
+        .into_iter()
+        .find(|(preset, _)| *preset == name)
+        .map(|(_, control)| control)
+}
@@ -0,0 +1,183 @@
+use chess::engine::piece::{Piece, PieceColor, PieceType};
+use egui::{Color32, Rect, TextureHandle, TextureOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Light and dark square colours of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardTheme {
+    pub name: &'static str,
+    pub light: Color32,
+    pub dark: Color32,
+}
+
+pub const BOARD_THEMES: [BoardTheme; 5] = [
+    BoardTheme {
+        name: "Green",
+        light: Color32::from_rgb(238, 238, 210),
+        dark: Color32::from_rgb(118, 150, 86),
+    },
+    BoardTheme {
+        name: "Brown",
+        light: Color32::from_rgb(240, 217, 181),
+        dark: Color32::from_rgb(181, 136, 99),
+    },
+    BoardTheme {
+        name: "Blue",
+        light: Color32::from_rgb(222, 227, 230),
+        dark: Color32::from_rgb(140, 162, 173),
+    },
+    BoardTheme {
+        name: "Grey",
+        light: Color32::from_rgb(220, 220, 220),
+        dark: Color32::from_rgb(150, 150, 150),
+    },
+    BoardTheme {
+        name: "Purple",
+        light: Color32::from_rgb(239, 239, 239),
+        dark: Color32::from_rgb(136, 119, 183),
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PieceSet {
+    Classic,
+    Silhouette,
+    Wood,
+    /// Unicode chess symbols in the egui font, as drawn before images.
+    Text,
+}
+
+impl PieceSet {
+    pub const ALL: [PieceSet; 4] = [
+        PieceSet::Classic,
+        PieceSet::Silhouette,
+        PieceSet::Wood,
+        PieceSet::Text,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceSet::Classic => "Classic",
+            PieceSet::Silhouette => "Silhouette",
+            PieceSet::Wood => "Wood",
+            PieceSet::Text => "Text symbols",
+        }
+    }
+
+    /// PNG data of every piece, white then black, each in the order king,
+    /// queen, rook, bishop, knight, pawn.
+    fn images(self) -> Option<&'static [&'static [u8]; 12]> {
+        macro_rules! piece_set {
+            ($dir:literal) => {
+                &[
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wK.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wQ.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wR.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wB.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wN.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/wP.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bK.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bQ.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bR.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bB.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bN.png")),
+                    include_bytes!(concat!("../../../assets/pieces/", $dir, "/bP.png")),
+                ]
+            };
+        }
+        match self {
+            PieceSet::Classic => Some(piece_set!("classic")),
+            PieceSet::Silhouette => Some(piece_set!("silhouette")),
+            PieceSet::Wood => Some(piece_set!("wood")),
+            PieceSet::Text => None,
+        }
+    }
+}
+
+fn image_index(piece: Piece) -> usize {
+    let kind = match piece.piece_type {
+        PieceType::King => 0,
+        PieceType::Queen => 1,
+        PieceType::Rook => 2,
+        PieceType::Bishop => 3,
+        PieceType::Knight => 4,
+        PieceType::Pawn => 5,
+    };
+    match piece.color {
+        PieceColor::White => kind,
+        PieceColor::Black => 6 + kind,
+    }
+}
+
+/// Draws pieces in the chosen set, turning the bundled images into
+/// textures the first time each one is needed.
+pub struct PieceRenderer {
+    pub set: PieceSet,
+    textures: HashMap<(PieceSet, usize), TextureHandle>,
+}
+
+impl PieceRenderer {
+    pub fn new(set: PieceSet) -> Self {
+        Self {
+            set,
+            textures: HashMap::new(),
+        }
+    }
+
+    fn texture(&mut self, ctx: &egui::Context, piece: Piece) -> Option<&TextureHandle> {
+        let images = self.set.images()?;
+        let index = image_index(piece);
+        let key = (self.set, index);
+        if !self.textures.contains_key(&key) {
+            let image = image::load_from_memory_with_format(images[index], image::ImageFormat::Png)
+                .expect("bundled piece images are valid PNG")
+                .to_rgba8();
+            let size = [image.width() as usize, image.height() as usize];
+            let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+            let name = format!("piece-{}-{}", self.set.name(), index);
+            let texture = ctx.load_texture(name, pixels, TextureOptions::LINEAR);
+            self.textures.insert(key, texture);
+        }
+        self.textures.get(&key)
+    }
+
+    /// Draws `piece` filling a square of side `size` centred on `center`.
+    pub fn draw(&mut self, painter: &egui::Painter, piece: Piece, center: egui::Pos2, size: f32) {
+        self.draw_faded(painter, piece, center, size, 1.0);
+    }
+
+    /// Same as `draw` with the piece partly transparent, from 0 (invisible)
+    /// to 1.
+    pub fn draw_faded(
+        &mut self,
+        painter: &egui::Painter,
+        piece: Piece,
+        center: egui::Pos2,
+        size: f32,
+        opacity: f32,
+    ) {
+        match self.texture(painter.ctx(), piece) {
+            Some(texture) => {
+                let rect = Rect::from_center_size(center, egui::Vec2::splat(size * 0.9));
+                let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                let tint = Color32::WHITE.gamma_multiply(opacity);
+                painter.image(texture.id(), rect, uv, tint);
+            }
+            None => {
+                let color = if piece.color == PieceColor::White {
+                    Color32::WHITE
+                } else {
+                    Color32::BLACK
+                };
+                painter.text(
+                    center,
+                    egui::Align2::CENTER_CENTER,
+                    format!("{}", piece).trim(),
+                    egui::FontId::proportional(size * 0.7),
+                    color.gamma_multiply(opacity),
+                );
+            }
+        }
+    }
+}
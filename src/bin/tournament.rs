@@ -0,0 +1,460 @@
+use chess::clock::{self, Stage, Timing};
+use chess::engine::{
+    chess_move::ChessMove,
+    error::PlayerError,
+    player::{ComputerPlayer, MoveRequest, Player},
+    skill::Skill,
+    uci_client::UciClient,
+};
+use chess::game::Game;
+use chess::tournament::{
+    Format, MatchSettings, Standings, TimeControl, Tournament,
+    openings::{Opening, load_openings},
+    stats::{Sprt, SprtState},
+};
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+// A week: longer than any game, short enough for clock sums not to overflow.
+const MAX_SECONDS: f64 = 7.0 * 24.0 * 3600.0;
+
+const USAGE: &str = "\
+usage: tournament --engine SPEC --engine SPEC [...] [options]
+
+engines:
+  --engine builtin [level=N | elo=N] [threads=N] [hash=MB] [name=NAME]
+  --engine cmd=PATH [arg=ARG ...] [option.NAME=VALUE ...] [name=NAME]
+
+options:
+  --gauntlet                  first engine plays every other one (default: round robin)
+  --rounds N                  game pairs per pairing, colours reversed (default 1)
+  --tc [MOVES/]SECONDS[+INC]  clock time control, e.g. 40/60 or 10+0.1
+  --st SECONDS                fixed time per move (default 0.1)
+  --depth N | --nodes N       fixed depth or node count per move
+  --timemargin MS             allowed clock overrun (default 50)
+  --openings FILE             EPD or PGN opening suite
+  --plies N                   moves to keep from PGN openings (default 16)
+  --pgnout FILE               append every game to FILE
+  --resign MOVES SCORE        resign adjudication
+  --draw PLY MOVES SCORE      draw adjudication after PLY plies
+  --maxplies N                draw games longer than N plies
+  --sprt ELO0 ELO1 ALPHA BETA stop early once the first engine's result is conclusive
+  --event NAME                PGN event tag";
+
+enum EngineSpec {
+    Builtin {
+        skill: Skill,
+        threads: usize,
+        hash_mb: Option<usize>,
+        name: Option<String>,
+    },
+    External {
+        path: PathBuf,
+        args: Vec<String>,
+        options: Vec<(String, String)>,
+        name: Option<String>,
+    },
+}
+
+struct Config {
+    engines: Vec<EngineSpec>,
+    settings: MatchSettings,
+    openings: Option<PathBuf>,
+    opening_plies: usize,
+    pgn_out: Option<PathBuf>,
+}
+
+/// A player shown under a name chosen on the command line.
+struct Renamed {
+    player: Box<dyn Player>,
+    name: String,
+}
+
+impl Player for Renamed {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), PlayerError> {
+        self.player.new_game()
+    }
+
+    fn choose_move(
+        &mut self,
+        game: &Game,
+        request: &MoveRequest,
+    ) -> Result<ChessMove, PlayerError> {
+        self.player.choose_move(game, request)
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.player.last_score()
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Config, String> {
+    let mut config = Config {
+        engines: Vec::new(),
+        settings: MatchSettings::default(),
+        openings: None,
+        opening_plies: 16,
+        pgn_out: None,
+    };
+    let mut i = 0;
+    // Values following `args[i]`, up to the next option.
+    let values = |i: usize| -> Vec<&str> {
+        args[i + 1..]
+            .iter()
+            .take_while(|arg| !arg.starts_with("--"))
+            .map(String::as_str)
+            .collect()
+    };
+    while i < args.len() {
+        let option = args[i].as_str();
+        let values = values(i);
+        let number = |index: usize| -> Result<f64, String> {
+            values
+                .get(index)
+                .and_then(|value| value.parse::<f64>().ok())
+                .ok_or_else(|| format!("{} expects a number", option))
+        };
+        match option {
+            "--engine" => config.engines.push(parse_engine(&values)?),
+            "--gauntlet" => config.settings.format = Format::Gauntlet,
+            "--rounds" => config.settings.rounds = number(0)?.max(1.0) as usize,
+            "--tc" => {
+                let text = values.first().ok_or("--tc expects a time control")?;
+                config.settings.time_control = parse_clock(text)?;
+            }
+            "--st" => {
+                let seconds = values.first().and_then(|text| parse_seconds(text));
+                let seconds = seconds.ok_or("--st expects a number of seconds")?;
+                config.settings.time_control = TimeControl::MoveTime(seconds);
+            }
+            "--depth" => config.settings.time_control = TimeControl::Depth(number(0)? as u8),
+            "--nodes" => config.settings.time_control = TimeControl::Nodes(number(0)? as u64),
+            "--timemargin" => {
+                config.settings.time_margin = Duration::from_millis(number(0)?.max(0.0) as u64)
+            }
+            "--openings" => {
+                config.openings = Some(PathBuf::from(
+                    values.first().ok_or("--openings expects a file")?,
+                ))
+            }
+            "--plies" => config.opening_plies = number(0)? as usize,
+            "--pgnout" => {
+                config.pgn_out = Some(PathBuf::from(
+                    values.first().ok_or("--pgnout expects a file")?,
+                ))
+            }
+            "--resign" => {
+                let adjudication = &mut config.settings.adjudication;
+                adjudication.resign_moves = number(0)? as u32;
+                adjudication.resign_score = Some(number(1)? as i32);
+            }
+            "--draw" => {
+                let adjudication = &mut config.settings.adjudication;
+                adjudication.draw_min_ply = number(0)? as usize;
+                adjudication.draw_moves = number(1)? as u32;
+                adjudication.draw_score = Some(number(2)? as i32);
+            }
+            "--maxplies" => config.settings.adjudication.max_plies = Some(number(0)? as usize),
+            "--sprt" => {
+                config.settings.sprt =
+                    Some(Sprt::new(number(0)?, number(1)?, number(2)?, number(3)?));
+            }
+            "--event" => config.settings.event = values.join(" "),
+            "--help" | "-h" => return Err(String::new()),
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+        i += 1 + values.len();
+    }
+    if config.engines.len() < 2 {
+        return Err("at least two engines are needed".to_string());
+    }
+    Ok(config)
+}
+
+fn parse_engine(values: &[&str]) -> Result<EngineSpec, String> {
+    let mut builtin = false;
+    let mut skill = Skill::full_strength();
+    let mut threads = 1;
+    let mut hash_mb = None;
+    let mut name = None;
+    let mut path = None;
+    let mut args = Vec::new();
+    let mut options = Vec::new();
+    for value in values {
+        if *value == "builtin" {
+            builtin = true;
+            continue;
+        }
+        let (key, setting) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value in engine spec, got '{}'", value))?;
+        let number = || {
+            setting
+                .parse::<usize>()
+                .map_err(|_| format!("{} expects a number", key))
+        };
+        match key {
+            "level" => skill = Skill::from_level(number()? as u8),
+            "elo" => skill = Skill::from_elo(number()? as u32),
+            "threads" => threads = number()?.max(1),
+            "hash" => hash_mb = Some(number()?.max(1)),
+            "name" => name = Some(setting.to_string()),
+            "cmd" => path = Some(PathBuf::from(setting)),
+            "arg" => args.push(setting.to_string()),
+            _ => match key.strip_prefix("option.") {
+                Some(option) => options.push((option.to_string(), setting.to_string())),
+                None => return Err(format!("unknown engine setting '{}'", key)),
+            },
+        }
+    }
+    match (builtin, path) {
+        (true, None) => Ok(EngineSpec::Builtin {
+            skill,
+            threads,
+            hash_mb,
+            name,
+        }),
+        (false, Some(path)) => Ok(EngineSpec::External {
+            path,
+            args,
+            options,
+            name,
+        }),
+        _ => Err("an engine is either 'builtin' or 'cmd=PATH'".to_string()),
+    }
+}
+
+/// `[MOVES/]SECONDS[+INCREMENT]`, as in `40/60`, `300` or `10+0.1`.
+fn parse_clock(text: &str) -> Result<TimeControl, String> {
+    let invalid = || format!("invalid time control '{}'", text);
+    let (moves, rest) = match text.split_once('/') {
+        Some((moves, rest)) => {
+            let moves = moves.parse::<u32>().ok().filter(|&moves| moves > 0);
+            (Some(moves.ok_or_else(invalid)?), rest)
+        }
+        None => (None, text),
+    };
+    let (base, increment) = rest.split_once('+').unwrap_or((rest, "0"));
+    Ok(TimeControl::Clock(clock::TimeControl {
+        stages: vec![Stage {
+            moves,
+            time: parse_seconds(base).ok_or_else(invalid)?,
+        }],
+        timing: Timing::Increment(parse_seconds(increment).ok_or_else(invalid)?),
+    }))
+}
+
+/// A time in seconds, refusing negative, infinite and absurdly long ones.
+fn parse_seconds(text: &str) -> Option<Duration> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|seconds| (0.0..=MAX_SECONDS).contains(seconds))
+        .map(Duration::from_secs_f64)
+}
+
+fn create_player(spec: &EngineSpec, seed: u64) -> Result<Box<dyn Player>, PlayerError> {
+    let (player, name): (Box<dyn Player>, &Option<String>) = match spec {
+        EngineSpec::Builtin {
+            skill,
+            threads,
+            hash_mb,
+            name,
+        } => {
+            let mut player = ComputerPlayer::with_seed(*skill, seed);
+            player.searcher_mut().set_threads(*threads);
+            if let Some(mb) = hash_mb {
+                player.searcher_mut().resize_hash(*mb);
+            }
+            (Box::new(player), name)
+        }
+        EngineSpec::External {
+            path,
+            args,
+            options,
+            name,
+        } => {
+            let mut client = UciClient::spawn(path, args)?;
+            for (option, value) in options {
+                client.set_option(option, value)?;
+            }
+            (Box::new(client), name)
+        }
+    };
+    Ok(match name {
+        Some(name) => Box::new(Renamed {
+            player,
+            name: name.clone(),
+        }),
+        None => player,
+    })
+}
+
+fn print_standings(standings: &Standings) {
+    println!(
+        "{:>4} {:<28} {:>8} {:>7} {:>6} {:>7} {:>16}",
+        "Rank", "Name", "Elo", "+/-", "Games", "Score", "W/D/L"
+    );
+    for (rank, player) in standings.ranking().into_iter().enumerate() {
+        let score = standings.score(player);
+        let (elo, margin) = score.elo().map_or_else(
+            || ("-".to_string(), "-".to_string()),
+            |estimate| {
+                (
+                    format!("{:+.0}", estimate.difference),
+                    format!("{:.0}", estimate.margin),
+                )
+            },
+        );
+        println!(
+            "{:>4} {:<28} {:>8} {:>7} {:>6} {:>6.1}% {:>16}",
+            rank + 1,
+            standings.name(player),
+            elo,
+            margin,
+            score.games(),
+            score.ratio().unwrap_or_default() * 100.0,
+            score.to_string()
+        );
+    }
+}
+
+fn run(config: Config) -> Result<(), String> {
+    let openings = match &config.openings {
+        Some(path) => load_openings(path, config.opening_plies).map_err(|e| e.to_string())?,
+        None => vec![Opening::default()],
+    };
+    let mut players = Vec::new();
+    for (index, spec) in config.engines.iter().enumerate() {
+        players.push(create_player(spec, index as u64).map_err(|e| e.to_string())?);
+    }
+    let mut pgn_out = match &config.pgn_out {
+        Some(path) => Some(
+            File::options()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("could not open '{}': {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+
+    let mut tournament =
+        Tournament::new(players, openings, config.settings).map_err(|e| e.to_string())?;
+    let total = tournament.schedule().len();
+    let sprt = tournament.settings().sprt;
+    let mut write_error = None;
+
+    let (standings, verdict) = tournament.run(&mut |report, standings| {
+        println!(
+            "Game {}/{}: {} vs {}: {} {{{}}}",
+            report.number,
+            total,
+            standings.name(report.pairing.white),
+            standings.name(report.pairing.black),
+            report.record.outcome.result,
+            report.record.reason
+        );
+        if let Some(file) = &mut pgn_out
+            && let Err(e) = writeln!(file, "{}", report.pgn)
+        {
+            write_error.get_or_insert(e);
+        }
+        let score = standings.head_to_head(0, 1);
+        if standings.player_count() == 2 {
+            let elo = score
+                .elo()
+                .map_or_else(|| "-".to_string(), |estimate| estimate.to_string());
+            print!(
+                "Score of {} vs {}: {}, Elo {}",
+                standings.name(0),
+                standings.name(1),
+                score,
+                elo
+            );
+            if let Some(sprt) = sprt {
+                let (lower, upper) = sprt.bounds();
+                print!(
+                    ", LLR {:.2} ({:.2}, {:.2})",
+                    sprt.llr(&standings.score(0)),
+                    lower,
+                    upper
+                );
+            }
+            println!();
+        }
+    });
+
+    println!();
+    print_standings(&standings);
+    if let Some(score) = standings.score(0).likelihood_of_superiority() {
+        println!("LOS of {}: {:.1}%", standings.name(0), score * 100.0);
+    }
+    match verdict {
+        Some(SprtState::AcceptH1) => println!("SPRT: H1 accepted"),
+        Some(SprtState::AcceptH0) => println!("SPRT: H0 accepted"),
+        Some(SprtState::Continue) | None => {}
+    }
+    match write_error {
+        Some(e) => Err(format!("could not write PGN: {}", e)),
+        None => Ok(()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("error: {}", message);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(message) = run(config) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clocks_are_parsed() {
+        assert_eq!(
+            parse_clock("40/60"),
+            Ok(TimeControl::Clock(clock::TimeControl {
+                stages: vec![Stage {
+                    moves: Some(40),
+                    time: Duration::from_secs(60),
+                }],
+                timing: Timing::Increment(Duration::ZERO),
+            }))
+        );
+        assert_eq!(
+            parse_clock("10+0.1"),
+            Ok(TimeControl::Clock(clock::TimeControl::fischer(
+                Duration::from_secs(10),
+                Duration::from_millis(100),
+            )))
+        );
+    }
+
+    #[test]
+    fn impossible_clocks_are_rejected() {
+        for text in ["0/1", "x/60", "inf", "NaN", "-5", "60+inf", "1e300", "60+"] {
+            assert!(parse_clock(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use chess::engine::{
+    chess_move::{ChessMove, parse_move},
+    piece::PieceColor,
+    player::ComputerPlayer,
+    position::Position,
+    search::{DEFAULT_HASH_MB, SearchLimits, SearchResult, StopSignal, allocate_time},
+    skill::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, Skill},
+};
+use std::io::{self, BufRead};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_THREADS: usize = 256;
+const MAX_HASH_MB: usize = 4096;
+const MAX_MULTI_PV: usize = 64;
+
+/// Parameters of a `go` command.
+#[derive(Default)]
+struct GoParams {
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Option<Duration>,
+    binc: Option<Duration>,
+    movestogo: Option<u32>,
+    depth: Option<u8>,
+    nodes: Option<u64>,
+    mate: Option<u8>,
+    movetime: Option<Duration>,
+    infinite: bool,
+    ponder: bool,
+    search_moves: Vec<ChessMove>,
+}
+
+impl GoParams {
+    fn parse(args: &[&str], position: &Position) -> GoParams {
+        let mut params = GoParams::default();
+        let millis = |value: Option<&&str>| {
+            value
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(|ms| Duration::from_millis(ms.max(0) as u64))
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let value = args.get(i + 1);
+            match args[i] {
+                "wtime" => params.wtime = millis(value),
+                "btime" => params.btime = millis(value),
+                "winc" => params.winc = millis(value),
+                "binc" => params.binc = millis(value),
+                "movetime" => params.movetime = millis(value),
+                "movestogo" => params.movestogo = value.and_then(|v| v.parse().ok()),
+                "depth" => params.depth = value.and_then(|v| v.parse().ok()),
+                "nodes" => params.nodes = value.and_then(|v| v.parse().ok()),
+                "mate" => params.mate = value.and_then(|v| v.parse().ok()),
+                "infinite" => {
+                    params.infinite = true;
+                    i += 1;
+                    continue;
+                }
+                "ponder" => {
+                    params.ponder = true;
+                    i += 1;
+                    continue;
+                }
+                "searchmoves" => {
+                    let legal = position.legal_moves();
+                    i += 1;
+                    while let Some(mv) = args.get(i).and_then(|text| parse_move(text).ok()) {
+                        if legal.contains(&mv) {
+                            params.search_moves.push(mv);
+                        }
+                        i += 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            i += 2;
+        }
+        params
+    }
+
+    /// Time the engine may spend on this move when it is not pondering.
+    fn time_budget(&self, side: PieceColor) -> Option<Duration> {
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+        let (remaining, increment) = match side {
+            PieceColor::White => (self.wtime, self.winc),
+            PieceColor::Black => (self.btime, self.binc),
+        };
+        remaining.map(|remaining| {
+            allocate_time(remaining, increment.unwrap_or_default(), self.movestogo)
+        })
+    }
+}
+
+struct RunningSearch {
+    handle: JoinHandle<()>,
+    // Holds `bestmove` back while the GUI still expects the engine to think
+    // (`go infinite`, or `go ponder` before `ponderhit`).
+    hold: Arc<AtomicBool>,
+    infinite: bool,
+    ponder_budget: Option<Duration>,
+    stop: StopSignal,
+}
+
+struct UciEngine {
+    player: Arc<Mutex<ComputerPlayer>>,
+    position: Position,
+    history: Vec<u64>,
+    search: Option<RunningSearch>,
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u32,
+}
+
+impl UciEngine {
+    fn new() -> Self {
+        Self {
+            player: Arc::new(Mutex::new(ComputerPlayer::default())),
+            position: Position::default(),
+            history: Vec::new(),
+            search: None,
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: MAX_ELO,
+        }
+    }
+
+    fn run(&mut self) {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else { break };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some((&command, args)) = tokens.split_first() else {
+                continue;
+            };
+            match command {
+                "uci" => self.identify(),
+                "isready" => println!("readyok"),
+                "ucinewgame" => {
+                    self.stop_search();
+                    self.player.lock().unwrap().new_game();
+                    self.position = Position::default();
+                    self.history.clear();
+                }
+                "setoption" => {
+                    self.stop_search();
+                    self.set_option(args);
+                }
+                "position" => {
+                    self.stop_search();
+                    self.set_position(args);
+                }
+                "go" => {
+                    self.stop_search();
+                    self.go(args);
+                }
+                "stop" => self.stop_search(),
+                "ponderhit" => self.ponder_hit(),
+                "quit" => {
+                    self.stop_search();
+                    break;
+                }
+                // `debug` and `register` need no handling.
+                _ => {}
+            }
+        }
+    }
+
+    fn identify(&self) {
+        println!(
+            "id name {} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        println!("id author julienbrs");
+        println!(
+            "option name Hash type spin default {} min 1 max {}",
+            DEFAULT_HASH_MB, MAX_HASH_MB
+        );
+        println!("option name Threads type spin default 1 min 1 max {MAX_THREADS}");
+        println!("option name MultiPV type spin default 1 min 1 max {MAX_MULTI_PV}");
+        println!("option name Ponder type check default false");
+        println!(
+            "option name Skill Level type spin default {0} min 0 max {0}",
+            MAX_SKILL_LEVEL
+        );
+        println!("option name UCI_LimitStrength type check default false");
+        println!(
+            "option name UCI_Elo type spin default {} min {} max {}",
+            MAX_ELO, MIN_ELO, MAX_ELO
+        );
+        println!("option name Clear Hash type button");
+        println!("uciok");
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        // setoption name <id with spaces> [value <x>]
+        let value_at = args.iter().position(|&token| token == "value");
+        let start = 1.min(args.len());
+        let name = args[start..value_at.unwrap_or(args.len()).max(start)].join(" ");
+        let value = value_at.map(|index| args[index + 1..].join(" "));
+        let number = value.as_deref().and_then(|v| v.parse::<usize>().ok());
+
+        let mut player = self.player.lock().unwrap();
+        match (name.to_ascii_lowercase().as_str(), number) {
+            ("hash", Some(mb)) => player.searcher_mut().resize_hash(mb.clamp(1, MAX_HASH_MB)),
+            ("threads", Some(threads)) => player
+                .searcher_mut()
+                .set_threads(threads.clamp(1, MAX_THREADS)),
+            ("multipv", Some(lines)) => player
+                .searcher_mut()
+                .set_multi_pv(lines.clamp(1, MAX_MULTI_PV)),
+            ("skill level", Some(level)) => {
+                self.skill_level = level.min(MAX_SKILL_LEVEL as usize) as u8
+            }
+            ("uci_elo", Some(elo)) => self.elo = elo as u32,
+            ("uci_limitstrength", _) => self.limit_strength = value.as_deref() == Some("true"),
+            ("clear hash", _) => player.searcher_mut().clear(),
+            ("ponder", _) => {}
+            _ => println!("info string unknown option '{}'", name),
+        }
+
+        let skill = if self.limit_strength {
+            Skill::from_elo(self.elo)
+        } else {
+            Skill::from_level(self.skill_level)
+        };
+        player.set_skill(skill);
+    }
+
+    fn set_position(&mut self, args: &[&str]) {
+        let moves_at = args.iter().position(|&token| token == "moves");
+        let setup = &args[..moves_at.unwrap_or(args.len())];
+        let position = match setup.first() {
+            Some(&"startpos") => Ok(Position::default()),
+            Some(&"fen") => Position::from_fen(&setup[1..].join(" ")),
+            _ => {
+                println!("info string expected 'startpos' or 'fen'");
+                return;
+            }
+        };
+        let mut position = match position {
+            Ok(position) => position,
+            Err(e) => {
+                println!("info string invalid FEN: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = position.validate() {
+            println!("info string illegal position: {}", e);
+            return;
+        }
+
+        let mut history = Vec::new();
+        for text in moves_at.map_or(&[][..], |index| &args[index + 1..]) {
+            match parse_move(text) {
+                Ok(mv) if position.is_legal(&mv) => {
+                    history.push(position.hash());
+                    position = position.play(&mv);
+                }
+                _ => {
+                    println!("info string illegal move '{}'", text);
+                    break;
+                }
+            }
+        }
+        self.position = position;
+        self.history = history;
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let params = GoParams::parse(args, &self.position);
+        let budget = params.time_budget(self.position.side_to_move());
+        let limits = SearchLimits {
+            depth: params
+                .depth
+                .or(params.mate.map(|moves| moves.saturating_mul(2))),
+            nodes: params.nodes,
+            // A ponder search has no deadline until the opponent plays the
+            // expected move; `ponderhit` arms the timer then.
+            movetime: if params.ponder || params.infinite {
+                None
+            } else {
+                budget
+            },
+            search_moves: params.search_moves,
+            stop: StopSignal::default(),
+        };
+
+        let stop = limits.stop.clone();
+        let hold = Arc::new(AtomicBool::new(params.infinite || params.ponder));
+        let player = Arc::clone(&self.player);
+        let position = self.position;
+        let history = self.history.clone();
+        let search_hold = Arc::clone(&hold);
+
+        let handle = thread::spawn(move || {
+            let (chosen, result) = player.lock().unwrap().think_with_progress(
+                &position,
+                &history,
+                &limits,
+                &mut print_info,
+            );
+            while search_hold.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            print_best_move(chosen, &result);
+        });
+
+        self.search = Some(RunningSearch {
+            handle,
+            hold,
+            infinite: params.infinite,
+            ponder_budget: if params.ponder { budget } else { None },
+            stop,
+        });
+    }
+
+    fn ponder_hit(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        if search.infinite {
+            return;
+        }
+        search.hold.store(false, Ordering::Relaxed);
+        if let Some(budget) = search.ponder_budget {
+            // A late timer only stops this search, which is over by then.
+            let stop = search.stop.clone();
+            thread::spawn(move || {
+                thread::sleep(budget);
+                stop.stop();
+            });
+        }
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.hold.store(false, Ordering::Relaxed);
+            search.stop.stop();
+            let _ = search.handle.join();
+        }
+    }
+}
+
+fn format_score(score: i32) -> String {
+    match SearchResult::mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+fn format_moves(moves: &[ChessMove]) -> String {
+    moves
+        .iter()
+        .map(|mv| mv.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn print_info(result: &SearchResult) {
+    let millis = result.elapsed.as_millis().max(1);
+    let nps = result.nodes as u128 * 1000 / millis;
+    for (index, line) in result.lines.iter().enumerate() {
+        println!(
+            "info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+            result.depth,
+            index + 1,
+            format_score(line.score),
+            result.nodes,
+            nps,
+            result.elapsed.as_millis(),
+            format_moves(&line.pv)
+        );
+    }
+}
+
+fn print_best_move(chosen: Option<ChessMove>, result: &SearchResult) {
+    let Some(best) = chosen else {
+        println!("bestmove 0000");
+        return;
+    };
+    let ponder = result
+        .lines
+        .iter()
+        .find(|line| line.pv.first() == Some(&best))
+        .and_then(|line| line.pv.get(1));
+    match ponder {
+        Some(ponder) => println!("bestmove {} ponder {}", best, ponder),
+        None => println!("bestmove {}", best),
+    }
+}
+
+fn main() {
+    UciEngine::new().run();
+}
@@ -0,0 +1,416 @@
+use chess::engine::{
+    chess_move::{ChessMove, parse_move},
+    piece::PieceColor,
+    player::ComputerPlayer,
+    position::Position,
+    search::{SearchLimits, SearchResult, StopSignal, allocate_time},
+    skill::{MAX_SKILL_LEVEL, Skill},
+};
+use chess::game::{Game, GameResult, Outcome, Termination};
+use std::io::{self, BufRead};
+use std::sync::{
+    Arc, Mutex,
+    mpsc::{self, Receiver, Sender},
+};
+use std::thread;
+use std::time::Duration;
+
+const MAX_THREADS: usize = 256;
+
+enum Event {
+    Command(String),
+    InputClosed,
+    Thought {
+        generation: u64,
+        best_move: Option<ChessMove>,
+    },
+}
+
+/// Clock settings from `level`: `moves` per session (0 for the whole game),
+/// `base` time per session and `increment` per move.
+struct Level {
+    moves: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+struct XBoardEngine {
+    player: Arc<Mutex<ComputerPlayer>>,
+    game: Game,
+    // `None` in force mode: moves are only recorded, never answered.
+    engine_color: Option<PieceColor>,
+    level: Level,
+    fixed_time: Option<Duration>,
+    max_depth: Option<u8>,
+    engine_time: Option<Duration>,
+    post: bool,
+    // Generation and stop signal of the search for the engine's move.
+    thinking: Option<(u64, StopSignal)>,
+    generation: u64,
+    events: Sender<Event>,
+}
+
+impl XBoardEngine {
+    fn new(events: Sender<Event>) -> Self {
+        Self {
+            player: Arc::new(Mutex::new(ComputerPlayer::default())),
+            game: Game::new(),
+            engine_color: Some(PieceColor::Black),
+            level: Level {
+                moves: 0,
+                base: Duration::from_secs(300),
+                increment: Duration::ZERO,
+            },
+            fixed_time: None,
+            max_depth: None,
+            engine_time: None,
+            post: false,
+            thinking: None,
+            generation: 0,
+            events,
+        }
+    }
+
+    fn run(&mut self, events: Receiver<Event>) {
+        for event in events {
+            match event {
+                Event::Command(line) => {
+                    if !self.handle_command(&line) {
+                        break;
+                    }
+                }
+                Event::InputClosed => break,
+                Event::Thought {
+                    generation,
+                    best_move,
+                } => {
+                    if self
+                        .thinking
+                        .as_ref()
+                        .is_some_and(|(current, _)| *current == generation)
+                    {
+                        self.thinking = None;
+                        self.play_engine_move(best_move);
+                    }
+                }
+            }
+        }
+        self.cancel_thinking();
+    }
+
+    /// Returns `false` once the engine should exit.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" => {}
+            "protover" => self.features(),
+            "new" => {
+                self.cancel_thinking();
+                self.game = Game::new();
+                self.engine_color = Some(PieceColor::Black);
+                self.fixed_time = None;
+                self.max_depth = None;
+                self.engine_time = None;
+                self.player.lock().unwrap().new_game();
+            }
+            "quit" => return false,
+            "force" => {
+                self.cancel_thinking();
+                self.engine_color = None;
+            }
+            "go" => {
+                self.cancel_thinking();
+                self.engine_color = Some(self.game.position().side_to_move());
+                self.think_if_engine_turn();
+            }
+            "playother" => {
+                self.cancel_thinking();
+                self.engine_color = Some(self.game.position().side_to_move().opposite());
+            }
+            "usermove" => {
+                if let Some(text) = args.first() {
+                    self.user_move(text);
+                }
+            }
+            "?" => {
+                if let Some((_, stop)) = &self.thinking {
+                    stop.stop();
+                }
+            }
+            "ping" => println!("pong {}", args.first().unwrap_or(&"")),
+            "level" => self.set_level(args),
+            "st" => {
+                self.fixed_time = args
+                    .first()
+                    .and_then(|seconds| seconds.parse::<f64>().ok())
+                    .map(Duration::from_secs_f64);
+            }
+            "sd" => self.max_depth = args.first().and_then(|depth| depth.parse().ok()),
+            "time" => self.engine_time = args.first().and_then(|cs| parse_centiseconds(cs)),
+            // The opponent's clock does not influence our time allocation.
+            "otim" => {}
+            "undo" => {
+                self.cancel_thinking();
+                self.game.undo();
+            }
+            "remove" => {
+                self.cancel_thinking();
+                self.game.undo();
+                self.game.undo();
+            }
+            "setboard" => {
+                self.cancel_thinking();
+                match Position::from_fen(&args.join(" ")) {
+                    Ok(position) => match position.validate() {
+                        Ok(()) => self.game = Game::from_position(position),
+                        Err(e) => println!("tellusererror Illegal position: {}", e),
+                    },
+                    Err(e) => println!("tellusererror Illegal position: {}", e),
+                }
+            }
+            "result" => {
+                self.cancel_thinking();
+                self.engine_color = None;
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "cores" => {
+                if let Some(threads) = args.first().and_then(|n| n.parse::<usize>().ok()) {
+                    self.player
+                        .lock()
+                        .unwrap()
+                        .searcher_mut()
+                        .set_threads(threads.clamp(1, MAX_THREADS));
+                }
+            }
+            "memory" => {
+                if let Some(mb) = args.first().and_then(|n| n.parse::<usize>().ok()) {
+                    self.player
+                        .lock()
+                        .unwrap()
+                        .searcher_mut()
+                        .resize_hash(mb.max(1));
+                }
+            }
+            "option" => self.set_option(&args.join(" ")),
+            // Hosts that did not accept `usermove=1` send bare moves.
+            _ => {
+                if parse_move(command).is_ok() {
+                    self.user_move(command);
+                } else {
+                    println!("Error (unknown command): {}", command);
+                }
+            }
+        }
+        true
+    }
+
+    fn features(&self) {
+        println!(
+            "feature myname=\"{} {}\" setboard=1 usermove=1 ping=1 time=1 draw=0 \
+             sigint=0 sigterm=0 colors=0 analyze=0 reuse=1 san=0 playother=1 \
+             smp=1 memory=1",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        );
+        println!(
+            "feature option=\"Skill Level -spin {0} 0 {0}\"",
+            MAX_SKILL_LEVEL
+        );
+        println!("feature done=1");
+    }
+
+    fn set_option(&mut self, setting: &str) {
+        let Some((name, value)) = setting.split_once('=') else {
+            return;
+        };
+        if name == "Skill Level"
+            && let Ok(level) = value.parse::<u8>()
+        {
+            self.player
+                .lock()
+                .unwrap()
+                .set_skill(Skill::from_level(level));
+        }
+    }
+
+    /// `level MPS BASE INC`, where BASE is minutes or `minutes:seconds`.
+    fn set_level(&mut self, args: &[&str]) {
+        let [moves, base, increment] = args else {
+            return;
+        };
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => minutes
+                .parse::<u64>()
+                .ok()
+                .zip(seconds.parse::<u64>().ok())
+                .map(|(m, s)| Duration::from_secs(m * 60 + s)),
+            None => base
+                .parse::<u64>()
+                .ok()
+                .map(|m| Duration::from_secs(m * 60)),
+        };
+        if let (Ok(moves), Some(base), Ok(increment)) =
+            (moves.parse(), base, increment.parse::<f64>())
+        {
+            self.level = Level {
+                moves,
+                base,
+                increment: Duration::from_secs_f64(increment),
+            };
+            self.fixed_time = None;
+        }
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.cancel_thinking();
+        let played = parse_move(text)
+            .map_err(|e| e.to_string())
+            .and_then(|mv| self.game.play(mv).map_err(|e| e.to_string()));
+        if let Err(e) = played {
+            println!("Illegal move ({}): {}", e, text);
+            return;
+        }
+        if !self.announce_outcome() {
+            self.think_if_engine_turn();
+        }
+    }
+
+    fn think_if_engine_turn(&mut self) {
+        let side = self.game.position().side_to_move();
+        if self.engine_color != Some(side) || self.game.outcome().is_some() {
+            return;
+        }
+
+        let limits = SearchLimits {
+            depth: self.max_depth,
+            movetime: self.fixed_time.or_else(|| self.time_budget()),
+            ..SearchLimits::default()
+        };
+        self.generation += 1;
+        let generation = self.generation;
+        self.thinking = Some((generation, limits.stop.clone()));
+
+        let player = Arc::clone(&self.player);
+        let events = self.events.clone();
+        let position = *self.game.position();
+        let history = self.game.history_hashes();
+        let post = self.post;
+        thread::spawn(move || {
+            let mut on_iteration = |result: &SearchResult| {
+                if post {
+                    print_thinking(result);
+                }
+            };
+            let (best_move, _) = player.lock().unwrap().think_with_progress(
+                &position,
+                &history,
+                &limits,
+                &mut on_iteration,
+            );
+            let _ = events.send(Event::Thought {
+                generation,
+                best_move,
+            });
+        });
+    }
+
+    fn time_budget(&self) -> Option<Duration> {
+        let remaining = self.engine_time.unwrap_or(self.level.base);
+        let moves_to_go = if self.level.moves > 0 {
+            let played = self.game.position().fullmove_number() - 1;
+            Some(self.level.moves - played % self.level.moves)
+        } else {
+            None
+        };
+        Some(allocate_time(remaining, self.level.increment, moves_to_go))
+    }
+
+    fn play_engine_move(&mut self, best_move: Option<ChessMove>) {
+        let Some(mv) = best_move else {
+            return;
+        };
+        if self.game.play(mv).is_ok() {
+            println!("move {}", mv);
+            self.announce_outcome();
+        }
+    }
+
+    /// Prints the result if the game just ended; returns whether it did.
+    fn announce_outcome(&self) -> bool {
+        match self.game.outcome() {
+            Some(outcome) => {
+                println!("{} {{{}}}", outcome.result, describe(outcome));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn cancel_thinking(&mut self) {
+        if let Some((_, stop)) = self.thinking.take() {
+            stop.stop();
+            // Wait for the search to release the player before going on.
+            drop(self.player.lock().unwrap());
+        }
+    }
+}
+
+fn describe(outcome: Outcome) -> String {
+    match (outcome.termination, outcome.result) {
+        (Termination::Checkmate, GameResult::WhiteWins) => "White mates".to_string(),
+        (Termination::Checkmate, _) => "Black mates".to_string(),
+        (termination, _) => {
+            let text = termination.to_string();
+            let mut chars = text.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => text,
+            }
+        }
+    }
+}
+
+fn parse_centiseconds(text: &str) -> Option<Duration> {
+    text.parse::<i64>()
+        .ok()
+        .map(|cs| Duration::from_millis(cs.max(0) as u64 * 10))
+}
+
+fn print_thinking(result: &SearchResult) {
+    // XBoard expects mate scores as 100000 + moves to mate.
+    let score = match SearchResult::mate_in(result.score) {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => result.score,
+    };
+    let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_string()).collect();
+    println!(
+        "{} {} {} {} {}",
+        result.depth,
+        score,
+        result.elapsed.as_millis() / 10,
+        result.nodes,
+        pv.join(" ")
+    );
+}
+
+fn main() {
+    let (sender, receiver) = mpsc::channel();
+    let input = sender.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if input.send(Event::Command(line)).is_err() {
+                return;
+            }
+        }
+        let _ = input.send(Event::InputClosed);
+    });
+
+    XBoardEngine::new(sender).run(receiver);
+}
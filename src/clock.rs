@@ -0,0 +1,535 @@
+use crate::engine::{
+    piece::{PieceColor, PieceType},
+    player::ClockTimes,
+    position::Position,
+};
+use crate::game::{GameResult, Outcome, Termination};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Part of a time control: `time` for the next `moves` moves, or for the
+/// rest of the game when `moves` is `None`. A last stage with a move count
+/// repeats, so `40/2h` gives two more hours every 40 moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stage {
+    pub moves: Option<u32>,
+    pub time: Duration,
+}
+
+/// How time is given back after each move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+    /// Fischer increment: a fixed bonus after every move.
+    Increment(Duration),
+    /// Bronstein delay: the time used is given back, up to the delay.
+    Bronstein(Duration),
+    /// Simple (US) delay: the clock only starts once the delay has passed.
+    Delay(Duration),
+    /// Time used by one player is added to the opponent's clock.
+    Hourglass,
+}
+
+/// A complete time control, e.g. 40 moves in 90 minutes then 30 minutes for
+/// the rest of the game, with a 30 second increment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub stages: Vec<Stage>,
+    pub timing: Timing,
+}
+
+impl TimeControl {
+    /// Single-stage control with a Fischer increment, like 5+3.
+    pub fn fischer(base: Duration, increment: Duration) -> Self {
+        Self {
+            stages: vec![Stage {
+                moves: None,
+                time: base,
+            }],
+            timing: Timing::Increment(increment),
+        }
+    }
+
+    pub fn bronstein(base: Duration, delay: Duration) -> Self {
+        Self {
+            timing: Timing::Bronstein(delay),
+            ..Self::fischer(base, Duration::ZERO)
+        }
+    }
+
+    pub fn delay(base: Duration, delay: Duration) -> Self {
+        Self {
+            timing: Timing::Delay(delay),
+            ..Self::fischer(base, Duration::ZERO)
+        }
+    }
+
+    pub fn hourglass(base: Duration) -> Self {
+        Self {
+            timing: Timing::Hourglass,
+            ..Self::fischer(base, Duration::ZERO)
+        }
+    }
+
+    /// Time on each clock before the first move.
+    pub fn initial_time(&self) -> Duration {
+        self.stages
+            .first()
+            .map_or(Duration::ZERO, |stage| stage.time)
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// Short notation such as `40/90m, 30m +30s` or `5m delay 5s`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stages: Vec<String> = self
+            .stages
+            .iter()
+            .map(|stage| match stage.moves {
+                Some(moves) => format!("{}/{}", moves, format_span(stage.time)),
+                None => format_span(stage.time),
+            })
+            .collect();
+        write!(f, "{}", stages.join(", "))?;
+        match self.timing {
+            Timing::Increment(increment) if increment.is_zero() => Ok(()),
+            Timing::Increment(increment) => write!(f, " +{}", format_span(increment)),
+            Timing::Bronstein(delay) => write!(f, " Bronstein {}", format_span(delay)),
+            Timing::Delay(delay) => write!(f, " delay {}", format_span(delay)),
+            Timing::Hourglass => write!(f, " hourglass"),
+        }
+    }
+}
+
+fn format_span(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 && seconds.is_multiple_of(60) {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}s", duration.as_secs_f64())
+    }
+}
+
+/// Two-sided game clock. Only one side's time runs at once; the first move
+/// of the game is not timed and starts the opponent's clock.
+///
+/// Every method that reads or changes the running time takes the current
+/// `Instant`, so the clock can be driven by a real or a simulated time.
+#[derive(Clone, Debug)]
+pub struct ChessClock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+    stage: [usize; 2],
+    // Moves made before the current stage began.
+    stage_start: [u32; 2],
+    active: Option<PieceColor>,
+    // Start of the running part of the current turn; `None` while paused.
+    running_since: Option<Instant>,
+    // Time spent on the current turn before the last pause.
+    turn_banked: Duration,
+    // How far a side may overrun its time before its flag falls.
+    grace: Duration,
+    flagged: Option<PieceColor>,
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        let initial = control.initial_time();
+        Self {
+            control,
+            remaining: [initial; 2],
+            moves_made: [0; 2],
+            stage: [0; 2],
+            stage_start: [0; 2],
+            active: None,
+            running_since: None,
+            turn_banked: Duration::ZERO,
+            grace: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    /// Lets a side overrun its time by `grace` before losing on time, to
+    /// allow for the lag of talking to an engine. Its clock still stops at
+    /// zero.
+    pub fn set_grace(&mut self, grace: Duration) {
+        self.grace = grace;
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// Side whose time is running (or would run if not paused).
+    pub fn active(&self) -> Option<PieceColor> {
+        self.active
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some() && self.flagged.is_none()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.active.is_some() && self.running_since.is_none() && self.flagged.is_none()
+    }
+
+    pub fn flagged(&self) -> Option<PieceColor> {
+        self.flagged
+    }
+
+    fn turn_elapsed(&self, now: Instant) -> Duration {
+        self.turn_banked
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |since| now.saturating_duration_since(since))
+    }
+
+    /// Time the active side has used so far this turn, after the delay.
+    fn turn_charge(&self, now: Instant) -> Duration {
+        let elapsed = self.turn_elapsed(now);
+        match self.control.timing {
+            Timing::Delay(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+
+    /// Time left on `color`'s clock at `now`.
+    pub fn remaining(&self, color: PieceColor, now: Instant) -> Duration {
+        let index = color as usize;
+        match self.active {
+            Some(active) if active == color => {
+                self.remaining[index].saturating_sub(self.turn_charge(now))
+            }
+            Some(_) if self.control.timing == Timing::Hourglass => {
+                self.remaining[index] + self.turn_charge(now)
+            }
+            _ => self.remaining[index],
+        }
+    }
+
+    /// Length of the stage `color` is currently playing in, used to judge
+    /// how low its time is.
+    pub fn stage_time(&self, color: PieceColor) -> Duration {
+        self.control
+            .stages
+            .get(self.stage[color as usize])
+            .or(self.control.stages.last())
+            .map_or(Duration::ZERO, |stage| stage.time)
+    }
+
+    /// Moves `color` has left to play before the next time control, if the
+    /// current stage has a move count.
+    pub fn moves_to_go(&self, color: PieceColor) -> Option<u32> {
+        let index = color as usize;
+        let stage_end = self.stage_end(index);
+        (stage_end != u32::MAX).then(|| stage_end.saturating_sub(self.moves_made[index]))
+    }
+
+    /// Both clocks as seen by a player asked for a move. Delays count as an
+    /// increment, which is what they are worth to a player who uses them.
+    pub fn times(&self, now: Instant) -> ClockTimes {
+        let increment = match self.control.timing {
+            Timing::Increment(time) | Timing::Bronstein(time) | Timing::Delay(time) => time,
+            Timing::Hourglass => Duration::ZERO,
+        };
+        let moves_to_go = self.active.and_then(|color| self.moves_to_go(color));
+        ClockTimes {
+            white: self.remaining(PieceColor::White, now),
+            black: self.remaining(PieceColor::Black, now),
+            white_increment: increment,
+            black_increment: increment,
+            moves_to_go,
+        }
+    }
+
+    /// Checks the running side's time, recording and returning it if it has
+    /// run out.
+    pub fn check_flag(&mut self, now: Instant) -> Option<PieceColor> {
+        if self.flagged.is_none()
+            && let Some(active) = self.active
+            && self.turn_charge(now) >= self.remaining[active as usize] + self.grace
+        {
+            // Charge the lost time so the flagged clock stays at zero.
+            let index = active as usize;
+            if self.control.timing == Timing::Hourglass {
+                self.remaining[active.opposite() as usize] += self.remaining[index];
+            }
+            self.remaining[index] = Duration::ZERO;
+            self.flagged = Some(active);
+            self.running_since = None;
+            self.turn_banked = Duration::ZERO;
+        }
+        self.flagged
+    }
+
+    /// Records that `mover` completed a move at `now` and starts the
+    /// opponent's time. Returns the flagged side if the move came too late.
+    pub fn press(&mut self, mover: PieceColor, now: Instant) -> Option<PieceColor> {
+        if self.flagged.is_some() {
+            return self.flagged;
+        }
+        if self.active == Some(mover) {
+            if self.check_flag(now).is_some() {
+                return self.flagged;
+            }
+            let index = mover as usize;
+            let charge = self.turn_charge(now);
+            let elapsed = self.turn_elapsed(now);
+            self.remaining[index] = self.remaining[index].saturating_sub(charge);
+            match self.control.timing {
+                Timing::Increment(increment) => self.remaining[index] += increment,
+                Timing::Bronstein(delay) => self.remaining[index] += elapsed.min(delay),
+                Timing::Delay(_) => {}
+                Timing::Hourglass => self.remaining[mover.opposite() as usize] += charge,
+            }
+            self.moves_made[index] += 1;
+            self.advance_stage(mover);
+        }
+        self.switch_to(mover.opposite(), now);
+        None
+    }
+
+    /// Adds the next stage's time once `color` completes the moves of the
+    /// current one, starting the last stage over if there is no next one.
+    fn advance_stage(&mut self, color: PieceColor) {
+        let index = color as usize;
+        let stage_end = self.stage_end(index);
+        if self.moves_made[index] < stage_end {
+            return;
+        }
+        let next = (self.stage[index] + 1).min(self.control.stages.len() - 1);
+        self.stage[index] = next;
+        self.stage_start[index] = stage_end;
+        self.remaining[index] += self.control.stages[next].time;
+    }
+
+    /// Move count at which the current stage of player `index` ends;
+    /// `u32::MAX` for a stage that lasts the rest of the game.
+    fn stage_end(&self, index: usize) -> u32 {
+        let moves = self.control.stages[self.stage[index]].moves;
+        self.stage_start[index].saturating_add(moves.unwrap_or(u32::MAX))
+    }
+
+    /// Gives the turn to `color` without charging anyone, e.g. after a
+    /// takeback. Keeps the clock paused if it was.
+    pub fn switch_to(&mut self, color: PieceColor, now: Instant) {
+        let paused = self.is_paused();
+        self.active = Some(color);
+        self.turn_banked = Duration::ZERO;
+        self.running_since = (!paused && self.flagged.is_none()).then_some(now);
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.turn_banked += now.saturating_duration_since(since);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if self.active.is_some() && self.flagged.is_none() && self.running_since.is_none() {
+            self.running_since = Some(now);
+        }
+    }
+}
+
+/// Result when `flagged` runs out of time in `position`: a loss, unless the
+/// opponent has too little material to ever checkmate.
+pub fn timeout_outcome(position: &Position, flagged: PieceColor) -> Outcome {
+    let winner = flagged.opposite();
+    let mut minor_pieces = 0;
+    let mut can_mate = false;
+    for piece in position.board().iter().flatten().flatten() {
+        if piece.color != winner {
+            continue;
+        }
+        match piece.piece_type {
+            PieceType::King => {}
+            PieceType::Bishop | PieceType::Knight => minor_pieces += 1,
+            _ => can_mate = true,
+        }
+    }
+    let result = if can_mate || minor_pieces >= 2 {
+        GameResult::win_for(winner)
+    } else {
+        GameResult::Draw
+    };
+    Outcome {
+        result,
+        termination: Termination::Timeout,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PieceColor::{Black, White};
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn minutes(minutes: u64) -> Duration {
+        seconds(minutes * 60)
+    }
+
+    /// A clock with White's untimed first move made at `start`, so Black's
+    /// time is running.
+    fn started(control: TimeControl, start: Instant) -> ChessClock {
+        let mut clock = ChessClock::new(control);
+        assert_eq!(clock.press(White, start), None);
+        assert_eq!(clock.active(), Some(Black));
+        clock
+    }
+
+    #[test]
+    fn first_move_is_not_timed() {
+        let start = Instant::now();
+        let mut clock = ChessClock::new(TimeControl::fischer(minutes(1), seconds(2)));
+        clock.press(White, start + seconds(30));
+        assert_eq!(clock.remaining(White, start + seconds(30)), minutes(1));
+    }
+
+    #[test]
+    fn increment_is_added_after_each_move() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::fischer(minutes(1), seconds(2)), start);
+        assert_eq!(clock.remaining(Black, start + seconds(5)), seconds(55));
+        clock.press(Black, start + seconds(5));
+        assert_eq!(clock.remaining(Black, start + seconds(5)), seconds(57));
+        assert_eq!(clock.remaining(White, start + seconds(5)), minutes(1));
+        assert_eq!(clock.active(), Some(White));
+    }
+
+    #[test]
+    fn bronstein_gives_back_at_most_the_delay() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::bronstein(minutes(1), seconds(3)), start);
+        clock.press(Black, start + seconds(2));
+        assert_eq!(clock.remaining(Black, start + seconds(2)), minutes(1));
+        clock.press(White, start + seconds(2));
+        clock.press(Black, start + seconds(7));
+        assert_eq!(clock.remaining(Black, start + seconds(7)), seconds(58));
+    }
+
+    #[test]
+    fn delay_passes_before_the_clock_runs() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::delay(minutes(1), seconds(5)), start);
+        assert_eq!(clock.remaining(Black, start + seconds(3)), minutes(1));
+        assert_eq!(clock.remaining(Black, start + seconds(8)), seconds(57));
+        clock.press(Black, start + seconds(8));
+        assert_eq!(clock.remaining(Black, start + seconds(8)), seconds(57));
+    }
+
+    #[test]
+    fn hourglass_moves_time_to_the_opponent() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::hourglass(minutes(1)), start);
+        assert_eq!(clock.remaining(White, start + seconds(4)), seconds(64));
+        clock.press(Black, start + seconds(10));
+        assert_eq!(clock.remaining(Black, start + seconds(10)), seconds(50));
+        assert_eq!(clock.remaining(White, start + seconds(10)), seconds(70));
+    }
+
+    #[test]
+    fn second_stage_starts_after_forty_moves() {
+        let control = TimeControl {
+            stages: vec![
+                Stage {
+                    moves: Some(40),
+                    time: minutes(90),
+                },
+                Stage {
+                    moves: None,
+                    time: minutes(30),
+                },
+            ],
+            timing: Timing::Increment(seconds(30)),
+        };
+        let start = Instant::now();
+        let mut clock = started(control, start);
+        clock.switch_to(White, start);
+        assert_eq!(clock.moves_to_go(White), Some(40));
+
+        // White spends a minute on each move; Black answers at once.
+        let mut now = start;
+        for _ in 0..39 {
+            now += minutes(1);
+            clock.press(White, now);
+            clock.press(Black, now);
+        }
+        assert_eq!(clock.moves_to_go(White), Some(1));
+        assert_eq!(clock.stage_time(White), minutes(90));
+        now += minutes(1);
+        clock.press(White, now);
+        assert_eq!(clock.moves_to_go(White), None);
+        assert_eq!(clock.stage_time(White), minutes(30));
+        // 90 - 40 + 40 increments of 30 seconds + 30.
+        assert_eq!(clock.remaining(White, now), minutes(100));
+        assert_eq!(clock.times(now).moves_to_go, Some(40 - 39));
+    }
+
+    #[test]
+    fn last_stage_with_moves_repeats() {
+        let control = TimeControl {
+            stages: vec![Stage {
+                moves: Some(2),
+                time: minutes(1),
+            }],
+            timing: Timing::Increment(Duration::ZERO),
+        };
+        let start = Instant::now();
+        let mut clock = started(control, start);
+        clock.press(Black, start + seconds(10));
+        assert_eq!(clock.moves_to_go(Black), Some(1));
+        clock.press(White, start + seconds(10));
+        clock.press(Black, start + seconds(20));
+        assert_eq!(clock.moves_to_go(Black), Some(2));
+        assert_eq!(clock.remaining(Black, start + seconds(20)), seconds(100));
+    }
+
+    #[test]
+    fn flag_falls_when_time_runs_out() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::fischer(seconds(10), seconds(1)), start);
+        assert_eq!(clock.check_flag(start + seconds(9)), None);
+        assert_eq!(clock.check_flag(start + seconds(10)), Some(Black));
+        assert_eq!(clock.remaining(Black, start + seconds(10)), Duration::ZERO);
+        assert!(!clock.is_running());
+        assert_eq!(clock.press(Black, start + seconds(11)), Some(Black));
+    }
+
+    #[test]
+    fn grace_allows_a_small_overrun() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::fischer(seconds(10), seconds(1)), start);
+        clock.set_grace(seconds(1));
+        let late = start + Duration::from_millis(10_500);
+        assert_eq!(clock.press(Black, late), None);
+        assert_eq!(clock.remaining(Black, late), seconds(1));
+
+        clock.press(White, late);
+        assert_eq!(clock.press(Black, late + seconds(2)), Some(Black));
+    }
+
+    #[test]
+    fn paused_time_is_not_charged() {
+        let start = Instant::now();
+        let mut clock = started(TimeControl::fischer(minutes(1), Duration::ZERO), start);
+        clock.pause(start + seconds(5));
+        assert!(clock.is_paused());
+        clock.resume(start + seconds(65));
+        assert_eq!(clock.remaining(Black, start + seconds(70)), seconds(50));
+    }
+
+    #[test]
+    fn timeout_without_mating_material_is_a_draw() {
+        let lone_knight = Position::from_fen("4k3/8/8/8/8/8/8/3NK3 w - - 0 1").unwrap();
+        assert_eq!(
+            timeout_outcome(&lone_knight, Black).result,
+            GameResult::Draw
+        );
+        let rook = Position::from_fen("4k3/8/8/8/8/8/8/3RK3 w - - 0 1").unwrap();
+        assert_eq!(timeout_outcome(&rook, Black).result, GameResult::WhiteWins);
+    }
+}
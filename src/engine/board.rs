@@ -14,7 +14,7 @@ pub fn print_board(board: &BoardGame) {
                 None => print!("· "),
             }
         }
-        println!("");
+        println!();
     }
     println!("  ----------------");
     println!("   a b c d e f g h");
@@ -54,12 +54,12 @@ impl BoardFactory {
         board_game[0][6] = Some(Piece::new(PieceType::Knight, PieceColor::Black));
         board_game[0][7] = Some(Piece::new(PieceType::Rook, PieceColor::Black));
 
-        for i in 0..8 {
-            board_game[1][i] = Some(Piece::new(PieceType::Pawn, PieceColor::Black));
+        for square in board_game[1].iter_mut() {
+            *square = Some(Piece::new(PieceType::Pawn, PieceColor::Black));
         }
 
-        for i in 0..8 {
-            board_game[6][i] = Some(Piece::new(PieceType::Pawn, PieceColor::White));
+        for square in board_game[6].iter_mut() {
+            *square = Some(Piece::new(PieceType::Pawn, PieceColor::White));
         }
 
         // Top rows (now Black pieces)
@@ -35,6 +35,17 @@ impl TryFrom<(u8, u8)> for Square {
     }
 }
 
+impl TryFrom<u8> for Square {
+    type Error = SquareError;
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if index < 64 {
+            Ok(Square(index))
+        } else {
+            Err(SquareError::OutOfBounds)
+        }
+    }
+}
+
 impl Square {
     pub fn row(self) -> usize {
         (self.0 / 8) as usize
@@ -48,10 +59,14 @@ impl Square {
         (self.row(), self.col())
     }
 
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
     pub fn offset(self, d_row: i32, d_col: i32) -> Option<Square> {
         let row = self.row() as i32 + d_row;
         let col = self.col() as i32 + d_col;
-        if row >= 0 && row < 8 && col >= 0 && col < 8 {
+        if (0..8).contains(&row) && (0..8).contains(&col) {
             Some(Square((row * 8 + col) as u8))
         } else {
             None
@@ -59,53 +74,93 @@ impl Square {
     }
 }
 
-fn parse_position(input: &str) -> Result<Square, SquareError> {
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Row 0 is the 8th rank, see `BoardFactory`
+        let col = (self.col() as u8 + b'a') as char;
+        let row = 8 - self.row();
+        write!(f, "{}{}", col, row)
+    }
+}
+
+pub fn parse_position(input: &str) -> Result<Square, SquareError> {
     let bytes = input.as_bytes();
 
     if bytes.len() != 2 {
         return Err(SquareError::InvalidLength);
     }
     let col = match bytes[0] {
-        b'a'..b'h' => bytes[0] - b'a',
+        b'a'..=b'h' => bytes[0] - b'a',
         _ => return Err(SquareError::InvalidColumn),
     };
 
     let row = match bytes[1] {
-        b'1'..b'8' => bytes[1] - b'1',
+        b'1'..=b'8' => b'8' - bytes[1],
         _ => return Err(SquareError::InvalidRow),
     };
 
     Square::try_from((row, col))
 }
 
+/// Parses a move in coordinate notation, e.g. `e2e4` or `e7e8q`.
 pub fn parse_move(input: &str) -> Result<ChessMove, SquareError> {
-    if input.len() != 4 {
+    if !input.is_ascii() || (input.len() != 4 && input.len() != 5) {
         return Err(SquareError::InvalidLength);
     }
 
     let from = parse_position(&input[0..2])?;
     let to = parse_position(&input[2..4])?;
+    let promotion = match input.as_bytes().get(4) {
+        None => None,
+        Some(b'q') => Some(PieceType::Queen),
+        Some(b'r') => Some(PieceType::Rook),
+        Some(b'b') => Some(PieceType::Bishop),
+        Some(b'n') => Some(PieceType::Knight),
+        Some(_) => return Err(SquareError::InvalidPromotion),
+    };
 
-    Ok(ChessMove { from, to })
+    Ok(ChessMove {
+        from,
+        to,
+        promotion,
+    })
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct ChessMove {
     pub from: Square,
     pub to: Square,
+    pub promotion: Option<PieceType>,
 }
 
-impl fmt::Display for ChessMove {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // Convert column number (0-7) back to chess notation (a-h)
-        let from_col = (self.from.col() as u8 + b'a') as char;
-        let to_col = (self.to.col() as u8 + b'a') as char;
+impl ChessMove {
+    pub fn new(from: Square, to: Square) -> Self {
+        Self {
+            from,
+            to,
+            promotion: None,
+        }
+    }
 
-        // Convert row number (0-7) back to chess notation (1-8)
-        let from_row = 8 - self.from.row();
-        let to_row = 8 - self.to.row();
+    pub fn with_promotion(from: Square, to: Square, piece_type: PieceType) -> Self {
+        Self {
+            from,
+            to,
+            promotion: Some(piece_type),
+        }
+    }
+}
 
-        write!(f, "{}{}{}{}", from_col, from_row, to_col, to_row)
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Knight) => write!(f, "n"),
+            _ => Ok(()),
+        }
     }
 }
 
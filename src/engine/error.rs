@@ -1,3 +1,4 @@
+use crate::engine::{chess_move::Square, piece::PieceColor};
 use thiserror::Error;
 
 #[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
@@ -13,4 +14,136 @@ pub enum SquareError {
 
     #[error("position is out of bounds (should be 0..=7)")]
     OutOfBounds,
+
+    #[error("invalid promotion piece (must be 'q', 'r', 'b' or 'n')")]
+    InvalidPromotion,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum FenError {
+    #[error("FEN must have at least 4 fields, got {0}")]
+    MissingFields(usize),
+
+    #[error("piece placement must describe 8 ranks")]
+    InvalidRankCount,
+
+    #[error("rank {0} does not describe exactly 8 squares")]
+    InvalidRankLength(usize),
+
+    #[error("unknown piece character '{0}'")]
+    InvalidPiece(char),
+
+    #[error("side to move must be 'w' or 'b', got '{0}'")]
+    InvalidSideToMove(String),
+
+    #[error("invalid castling field '{0}'")]
+    InvalidCastling(String),
+
+    #[error("invalid en passant square '{0}'")]
+    InvalidEnPassant(String),
+
+    #[error("invalid move counter '{0}'")]
+    InvalidCounter(String),
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum GameError {
+    #[error("illegal move '{0}' in this position")]
+    IllegalMove(String),
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SanError {
+    #[error("'{0}' is not a move in algebraic notation")]
+    InvalidSyntax(String),
+
+    #[error("'{0}' is not a legal move in this position")]
+    IllegalMove(String),
+
+    #[error("'{0}' matches more than one legal move")]
+    AmbiguousMove(String),
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PlayerError {
+    #[error("could not start engine: {0}")]
+    Spawn(String),
+
+    #[error("engine did not answer within {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error("engine process exited unexpectedly")]
+    Crashed,
+
+    #[error("unexpected engine output: {0}")]
+    Protocol(String),
+
+    #[error("engine played an illegal move '{0}'")]
+    IllegalMove(String),
+
+    #[error("no legal move to play")]
+    NoMove,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    #[error("malformed tag pair '{0}'")]
+    InvalidTag(String),
+
+    #[error("invalid FEN tag: {0}")]
+    InvalidFen(#[from] FenError),
+
+    #[error("move {ply}: {source}")]
+    InvalidMove { ply: usize, source: SanError },
+
+    #[error("unterminated {0}")]
+    Unterminated(&'static str),
+
+    #[error("unexpected '{0}'")]
+    Unexpected(char),
+}
+
+#[derive(Debug, Error)]
+pub enum TournamentError {
+    #[error("could not read '{path}': {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("invalid opening on line {line}: {message}")]
+    InvalidOpening { line: usize, message: String },
+
+    #[error("opening game {game} starts from an illegal position: {source}")]
+    IllegalOpeningGame { game: usize, source: PositionError },
+
+    #[error("invalid PGN: {0}")]
+    Pgn(#[from] PgnError),
+
+    #[error("the opening suite is empty")]
+    NoOpenings,
+
+    #[error("a tournament needs at least two players")]
+    NotEnoughPlayers,
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    #[error("{0:?} must have exactly one king")]
+    KingCount(PieceColor),
+
+    #[error("{0:?} has more than 16 pieces or more than 8 pawns")]
+    TooManyPieces(PieceColor),
+
+    #[error("pawns cannot stand on the first or last rank")]
+    PawnOnBackRank,
+
+    #[error("the side that just moved is in check")]
+    OpponentInCheck,
+
+    #[error("castling rights need the king and rook on their original squares")]
+    InvalidCastling,
+
+    #[error("en passant square {0} does not follow a double pawn push")]
+    InvalidEnPassant(Square),
 }
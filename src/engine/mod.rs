@@ -1,4 +1,14 @@
 pub mod board;
 pub mod chess_move;
+pub mod error;
+pub mod eval;
+pub mod movegen;
 pub mod piece;
-pub mod error;
\ No newline at end of file
+pub mod player;
+pub mod position;
+pub mod san;
+pub mod search;
+pub mod skill;
+pub mod tt;
+pub mod uci_client;
+pub mod zobrist;
@@ -0,0 +1,330 @@
+use crate::engine::{
+    board::BoardGame,
+    chess_move::{ChessMove, Square},
+    piece::{Piece, PieceColor, PieceType},
+    position::{CastlingRights, Position, home_row},
+};
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+fn pawn_direction(color: PieceColor) -> i32 {
+    match color {
+        PieceColor::White => -1,
+        PieceColor::Black => 1,
+    }
+}
+
+fn pawn_start_row(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 6,
+        PieceColor::Black => 1,
+    }
+}
+
+fn all_squares() -> impl Iterator<Item = Square> {
+    (0..64u8).map(|index| Square::try_from(index).unwrap())
+}
+
+const PROMOTION_PIECES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+];
+
+fn push_pawn_move(from: Square, to: Square, moves: &mut Vec<ChessMove>) {
+    if to.row() == 0 || to.row() == 7 {
+        for piece_type in PROMOTION_PIECES {
+            moves.push(ChessMove::with_promotion(from, to, piece_type));
+        }
+    } else {
+        moves.push(ChessMove::new(from, to));
+    }
+}
+
+/// Moves that follow each piece's movement rules, without checking whether
+/// they leave the mover's own king in check.
+pub fn generate_pseudo_legal_moves(position: &Position) -> Vec<ChessMove> {
+    let board = position.board();
+    let color = position.side_to_move();
+    let mut moves = Vec::with_capacity(48);
+
+    for from in all_squares() {
+        let piece = match board[from.row()][from.col()] {
+            Some(piece) if piece.color == color => piece,
+            _ => continue,
+        };
+
+        match piece.piece_type {
+            PieceType::Pawn => {
+                let direction = pawn_direction(color);
+                if let Some(one_step) = from.offset(direction, 0)
+                    && board[one_step.row()][one_step.col()].is_none()
+                {
+                    push_pawn_move(from, one_step, &mut moves);
+                    if from.row() == pawn_start_row(color)
+                        && let Some(two_steps) = from.offset(2 * direction, 0)
+                        && board[two_steps.row()][two_steps.col()].is_none()
+                    {
+                        moves.push(ChessMove::new(from, two_steps));
+                    }
+                }
+                for d_col in [-1, 1] {
+                    if let Some(to) = from.offset(direction, d_col) {
+                        match board[to.row()][to.col()] {
+                            Some(target) if target.color != color => {
+                                push_pawn_move(from, to, &mut moves)
+                            }
+                            None if Some(to) == position.en_passant() => {
+                                moves.push(ChessMove::new(from, to))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            PieceType::Knight => push_steps(board, color, from, &KNIGHT_OFFSETS, &mut moves),
+            PieceType::King => {
+                push_steps(board, color, from, &KING_OFFSETS, &mut moves);
+                push_castling(position, from, &mut moves);
+            }
+            PieceType::Rook => push_slides(board, color, from, &ROOK_DIRECTIONS, &mut moves),
+            PieceType::Bishop => push_slides(board, color, from, &BISHOP_DIRECTIONS, &mut moves),
+            PieceType::Queen => {
+                push_slides(board, color, from, &ROOK_DIRECTIONS, &mut moves);
+                push_slides(board, color, from, &BISHOP_DIRECTIONS, &mut moves);
+            }
+        }
+    }
+
+    moves
+}
+
+fn push_steps(
+    board: &BoardGame,
+    color: PieceColor,
+    from: Square,
+    offsets: &[(i32, i32)],
+    moves: &mut Vec<ChessMove>,
+) {
+    for &(d_row, d_col) in offsets {
+        if let Some(to) = from.offset(d_row, d_col) {
+            match board[to.row()][to.col()] {
+                Some(target) if target.color == color => {}
+                _ => moves.push(ChessMove::new(from, to)),
+            }
+        }
+    }
+}
+
+fn push_slides(
+    board: &BoardGame,
+    color: PieceColor,
+    from: Square,
+    directions: &[(i32, i32)],
+    moves: &mut Vec<ChessMove>,
+) {
+    for &(d_row, d_col) in directions {
+        let mut current = from;
+        while let Some(to) = current.offset(d_row, d_col) {
+            match board[to.row()][to.col()] {
+                None => moves.push(ChessMove::new(from, to)),
+                Some(target) => {
+                    if target.color != color {
+                        moves.push(ChessMove::new(from, to));
+                    }
+                    break;
+                }
+            }
+            current = to;
+        }
+    }
+}
+
+/// Castling is generated with the king moving two squares towards the rook.
+/// The king may not castle out of, through or into check.
+fn push_castling(position: &Position, from: Square, moves: &mut Vec<ChessMove>) {
+    let board = position.board();
+    let color = position.side_to_move();
+    let row = home_row(color);
+    if from.to_tuple() != (row, 4) {
+        return;
+    }
+    let enemy = color.opposite();
+    let square = |col: usize| Square::try_from((row as u8, col as u8)).unwrap();
+    let is_empty = |cols: &[usize]| cols.iter().all(|&col| board[row][col].is_none());
+    let is_safe = |cols: &[usize]| {
+        cols.iter()
+            .all(|&col| !is_square_attacked(board, square(col), enemy))
+    };
+    let rook = Some(Piece::new(PieceType::Rook, color));
+
+    let rights = position.castling_rights();
+    if rights.contains(CastlingRights::kingside(color))
+        && board[row][7] == rook
+        && is_empty(&[5, 6])
+        && is_safe(&[4, 5, 6])
+    {
+        moves.push(ChessMove::new(from, square(6)));
+    }
+    if rights.contains(CastlingRights::queenside(color))
+        && board[row][0] == rook
+        && is_empty(&[1, 2, 3])
+        && is_safe(&[4, 3, 2])
+    {
+        moves.push(ChessMove::new(from, square(2)));
+    }
+}
+
+pub fn generate_legal_moves(position: &Position) -> Vec<ChessMove> {
+    let color = position.side_to_move();
+    generate_pseudo_legal_moves(position)
+        .into_iter()
+        .filter(|mv| !position.play(mv).is_in_check(color))
+        .collect()
+}
+
+pub fn find_king(board: &BoardGame, color: PieceColor) -> Option<Square> {
+    all_squares().find(|square| {
+        matches!(
+            board[square.row()][square.col()],
+            Some(piece) if piece.piece_type == PieceType::King && piece.color == color
+        )
+    })
+}
+
+pub fn is_in_check(board: &BoardGame, color: PieceColor) -> bool {
+    match find_king(board, color) {
+        Some(king) => is_square_attacked(board, king, color.opposite()),
+        None => false,
+    }
+}
+
+pub fn is_square_attacked(board: &BoardGame, square: Square, by: PieceColor) -> bool {
+    let holds = |target: Option<Square>, kinds: &[PieceType]| {
+        target.is_some_and(|sq| {
+            matches!(
+                board[sq.row()][sq.col()],
+                Some(piece) if piece.color == by && kinds.contains(&piece.piece_type)
+            )
+        })
+    };
+
+    // A pawn attacks diagonally forward, so look one row "behind" the square
+    // from the attacker's point of view.
+    let pawn_row = -pawn_direction(by);
+    if holds(square.offset(pawn_row, -1), &[PieceType::Pawn])
+        || holds(square.offset(pawn_row, 1), &[PieceType::Pawn])
+    {
+        return true;
+    }
+
+    if KNIGHT_OFFSETS
+        .iter()
+        .any(|&(r, c)| holds(square.offset(r, c), &[PieceType::Knight]))
+    {
+        return true;
+    }
+
+    if KING_OFFSETS
+        .iter()
+        .any(|&(r, c)| holds(square.offset(r, c), &[PieceType::King]))
+    {
+        return true;
+    }
+
+    let slider_hits = |directions: &[(i32, i32)], kinds: &[PieceType]| {
+        directions.iter().any(|&(d_row, d_col)| {
+            let mut current = square;
+            while let Some(next) = current.offset(d_row, d_col) {
+                if let Some(piece) = board[next.row()][next.col()] {
+                    return piece.color == by && kinds.contains(&piece.piece_type);
+                }
+                current = next;
+            }
+            false
+        })
+    };
+
+    slider_hits(&ROOK_DIRECTIONS, &[PieceType::Rook, PieceType::Queen])
+        || slider_hits(&BISHOP_DIRECTIONS, &[PieceType::Bishop, PieceType::Queen])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::position::Position;
+
+    fn perft(position: &Position, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = position.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|mv| perft(&position.play(mv), depth - 1))
+            .sum()
+    }
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let position = Position::from_fen(fen).unwrap();
+        for (depth, &nodes) in (1..).zip(expected) {
+            assert_eq!(perft(&position, depth), nodes, "{} at depth {}", fen, depth);
+        }
+    }
+
+    #[test]
+    fn perft_start_position() {
+        assert_perft(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902],
+        );
+    }
+
+    #[test]
+    fn perft_castling_and_pins() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039],
+        );
+    }
+
+    #[test]
+    fn perft_en_passant_and_discovered_checks() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812],
+        );
+    }
+
+    #[test]
+    fn perft_promotions() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use std::fmt;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PieceColor {
     White,
     Black,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PieceType {
     King,
     Queen,
@@ -16,7 +16,7 @@ pub enum PieceType {
     Pawn,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: PieceColor,
@@ -77,4 +77,35 @@ impl Piece {
     pub fn new(piece_type: PieceType, color: PieceColor) -> Piece {
         Piece { piece_type, color }
     }
+
+    /// Index in 0..12, used to address per-piece tables (white pieces first).
+    pub fn index(self) -> usize {
+        let color_offset = match self.color {
+            PieceColor::White => 0,
+            PieceColor::Black => 6,
+        };
+        color_offset + self.piece_type.index()
+    }
+}
+
+impl PieceColor {
+    pub fn opposite(self) -> PieceColor {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+}
+
+impl PieceType {
+    pub fn index(self) -> usize {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 1,
+            PieceType::Rook => 2,
+            PieceType::Knight => 3,
+            PieceType::Bishop => 4,
+            PieceType::Pawn => 5,
+        }
+    }
 }
@@ -0,0 +1,215 @@
+use crate::engine::{
+    chess_move::ChessMove,
+    error::PlayerError,
+    piece::PieceColor,
+    position::Position,
+    search::{SearchLimits, SearchResult, Searcher, allocate_time},
+    skill::Skill,
+};
+use crate::game::Game;
+use rand::{SeedableRng, rngs::StdRng};
+use std::time::Duration;
+
+/// Remaining time on both clocks when a move is requested.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClockTimes {
+    pub white: Duration,
+    pub black: Duration,
+    pub white_increment: Duration,
+    pub black_increment: Duration,
+    pub moves_to_go: Option<u32>,
+}
+
+impl ClockTimes {
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        match color {
+            PieceColor::White => self.white,
+            PieceColor::Black => self.black,
+        }
+    }
+
+    pub fn increment(&self, color: PieceColor) -> Duration {
+        match color {
+            PieceColor::White => self.white_increment,
+            PieceColor::Black => self.black_increment,
+        }
+    }
+}
+
+/// What a player is allowed to spend on one move: fixed search limits,
+/// the game clocks, or both.
+#[derive(Clone, Debug, Default)]
+pub struct MoveRequest {
+    pub limits: SearchLimits,
+    pub clock: Option<ClockTimes>,
+}
+
+impl MoveRequest {
+    /// Search limits for `color`, with the clock turned into a move time.
+    pub fn limits_for(&self, color: PieceColor) -> SearchLimits {
+        let mut limits = self.limits.clone();
+        if let (None, Some(clock)) = (limits.movetime, self.clock) {
+            limits.movetime = Some(allocate_time(
+                clock.remaining(color),
+                clock.increment(color),
+                clock.moves_to_go,
+            ));
+        }
+        limits
+    }
+}
+
+/// Anything that can pick moves in a game: the built-in engine or an
+/// external one.
+pub trait Player {
+    fn name(&self) -> String;
+
+    /// Called before the first move of every game.
+    fn new_game(&mut self) -> Result<(), PlayerError> {
+        Ok(())
+    }
+
+    /// Picks a move for the side to move in `game`.
+    fn choose_move(&mut self, game: &Game, request: &MoveRequest)
+    -> Result<ChessMove, PlayerError>;
+
+    /// Evaluation behind the last chosen move, in centipawns from the
+    /// player's point of view, if it reports one.
+    fn last_score(&self) -> Option<i32> {
+        None
+    }
+}
+
+/// The built-in engine as an opponent, playing at a chosen `Skill`.
+pub struct ComputerPlayer {
+    searcher: Searcher,
+    skill: Skill,
+    rng: StdRng,
+    last_score: Option<i32>,
+}
+
+impl ComputerPlayer {
+    pub fn new(skill: Skill) -> Self {
+        Self {
+            searcher: Searcher::default(),
+            skill,
+            rng: StdRng::from_os_rng(),
+            last_score: None,
+        }
+    }
+
+    /// Same as `new` but with reproducible move choices at reduced skill.
+    pub fn with_seed(skill: Skill, seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            ..Self::new(skill)
+        }
+    }
+
+    pub fn skill(&self) -> Skill {
+        self.skill
+    }
+
+    pub fn set_skill(&mut self, skill: Skill) {
+        self.skill = skill;
+    }
+
+    pub fn searcher_mut(&mut self) -> &mut Searcher {
+        &mut self.searcher
+    }
+
+    pub fn new_game(&mut self) {
+        self.searcher.clear();
+    }
+
+    /// Searches `position` within `limits`, tightened to the skill level,
+    /// and returns the move the player decides to play with the search
+    /// result it was picked from. `history` is passed on to the search.
+    pub fn think(
+        &mut self,
+        position: &Position,
+        history: &[u64],
+        limits: &SearchLimits,
+    ) -> (Option<ChessMove>, SearchResult) {
+        self.think_with_progress(position, history, limits, &mut |_| {})
+    }
+
+    pub fn think_with_progress(
+        &mut self,
+        position: &Position,
+        history: &[u64],
+        limits: &SearchLimits,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> (Option<ChessMove>, SearchResult) {
+        // A weakened player needs extra lines to choose from. They are kept
+        // out of the progress reports, which show only as many lines as the
+        // caller's own MultiPV setting, restored afterwards.
+        let requested_lines = self.searcher.multi_pv();
+        self.searcher
+            .set_multi_pv(requested_lines.max(self.skill.multi_pv()));
+        let mut report = |result: &SearchResult| {
+            if result.lines.len() <= requested_lines {
+                on_iteration(result);
+            } else {
+                let mut shown = result.clone();
+                shown.lines.truncate(requested_lines);
+                on_iteration(&shown);
+            }
+        };
+        let result = self.searcher.search_with_progress(
+            position,
+            history,
+            &self.skill.restrict(limits),
+            &mut report,
+        );
+        self.searcher.set_multi_pv(requested_lines);
+        let chosen = self.skill.pick_move(&result, &mut self.rng);
+        (chosen, result)
+    }
+}
+
+impl Player for ComputerPlayer {
+    fn name(&self) -> String {
+        if self.skill.is_full_strength() {
+            format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+        } else {
+            format!(
+                "{} {} (level {})",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION"),
+                self.skill.level()
+            )
+        }
+    }
+
+    fn new_game(&mut self) -> Result<(), PlayerError> {
+        ComputerPlayer::new_game(self);
+        Ok(())
+    }
+
+    fn choose_move(
+        &mut self,
+        game: &Game,
+        request: &MoveRequest,
+    ) -> Result<ChessMove, PlayerError> {
+        let position = game.position();
+        let limits = request.limits_for(position.side_to_move());
+        let (chosen, result) = self.think(position, &game.history_hashes(), &limits);
+        self.last_score = result
+            .lines
+            .iter()
+            .find(|line| line.pv.first() == chosen.as_ref())
+            .map_or(Some(result.score), |line| Some(line.score));
+        chosen.ok_or(PlayerError::NoMove)
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_score
+    }
+}
+
+impl Default for ComputerPlayer {
+    fn default() -> Self {
+        Self::new(Skill::default())
+    }
+}
@@ -0,0 +1,908 @@
+use crate::engine::{
+    board::{BoardFactory, BoardGame, BoardPosition},
+    chess_move::{ChessMove, Square, parse_position},
+    error::{FenError, PositionError},
+    movegen,
+    piece::{Piece, PieceColor, PieceType},
+    zobrist,
+};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Which castling moves are still available, one bit per king side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct CastlingRights(u8);
+
+impl CastlingRights {
+    pub const WHITE_KINGSIDE: CastlingRights = CastlingRights(1);
+    pub const WHITE_QUEENSIDE: CastlingRights = CastlingRights(2);
+    pub const BLACK_KINGSIDE: CastlingRights = CastlingRights(4);
+    pub const BLACK_QUEENSIDE: CastlingRights = CastlingRights(8);
+    pub const NONE: CastlingRights = CastlingRights(0);
+    pub const ALL: CastlingRights = CastlingRights(15);
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: CastlingRights) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: CastlingRights) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: CastlingRights) {
+        self.0 &= !other.0;
+    }
+
+    pub fn kingside(color: PieceColor) -> CastlingRights {
+        match color {
+            PieceColor::White => Self::WHITE_KINGSIDE,
+            PieceColor::Black => Self::BLACK_KINGSIDE,
+        }
+    }
+
+    pub fn queenside(color: PieceColor) -> CastlingRights {
+        match color {
+            PieceColor::White => Self::WHITE_QUEENSIDE,
+            PieceColor::Black => Self::BLACK_QUEENSIDE,
+        }
+    }
+}
+
+/// Row of `color`'s back rank in `BoardGame` coordinates.
+pub fn home_row(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 7,
+        PieceColor::Black => 0,
+    }
+}
+
+/// Castling rights allowed by `board`: one for every rook that still stands
+/// on its original square next to its king on its own.
+pub fn possible_castling(board: &BoardGame) -> CastlingRights {
+    let mut castling = CastlingRights::NONE;
+    for color in [PieceColor::White, PieceColor::Black] {
+        let row = home_row(color);
+        let is = |col: usize, piece_type: PieceType| {
+            board[row][col] == Some(Piece::new(piece_type, color))
+        };
+        if is(4, PieceType::King) {
+            if is(7, PieceType::Rook) {
+                castling.insert(CastlingRights::kingside(color));
+            }
+            if is(0, PieceType::Rook) {
+                castling.insert(CastlingRights::queenside(color));
+            }
+        }
+    }
+    castling
+}
+
+/// En passant squares allowed by `board` with `side_to_move` to move: those
+/// a pawn of the other side may just have skipped with a double push, and
+/// that a pawn of the side to move can capture on.
+pub fn possible_en_passant(board: &BoardGame, side_to_move: PieceColor) -> Vec<Square> {
+    let (row, d_row) = match side_to_move {
+        PieceColor::White => (2, 1),
+        PieceColor::Black => (5, -1),
+    };
+    let at = |square: Option<Square>| square.and_then(|square| board[square.row()][square.col()]);
+    let pawn = |color| Some(Piece::new(PieceType::Pawn, color));
+    (0..8)
+        .filter_map(|col| Square::try_from((row as u8, col)).ok())
+        .filter(|&square| {
+            at(Some(square)).is_none()
+                && at(square.offset(-d_row, 0)).is_none()
+                && at(square.offset(d_row, 0)) == pawn(side_to_move.opposite())
+                && [-1, 1]
+                    .iter()
+                    .any(|&d_col| at(square.offset(d_row, d_col)) == pawn(side_to_move))
+        })
+        .collect()
+}
+
+/// Everything needed to continue a game from a given point: the board, the
+/// side to move, castling and en passant state and the move counters. The
+/// Zobrist hash is updated incrementally so the search can use it as a
+/// transposition-table key.
+#[derive(Clone, Copy)]
+pub struct Position {
+    board: BoardGame,
+    side_to_move: PieceColor,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+}
+
+impl Position {
+    /// Builds a position from a bare board. Castling rights are granted
+    /// wherever king and rook still stand on their original squares.
+    pub fn new(board: BoardGame, side_to_move: PieceColor) -> Self {
+        Self::from_parts(board, side_to_move, possible_castling(&board), None, 0, 1)
+    }
+
+    /// Builds a position from every part of its state, as a FEN would.
+    /// Nothing is checked; see `validate`.
+    pub fn from_parts(
+        board: BoardGame,
+        side_to_move: PieceColor,
+        castling: CastlingRights,
+        en_passant: Option<Square>,
+        halfmove_clock: u32,
+        fullmove_number: u32,
+    ) -> Self {
+        Self {
+            board,
+            side_to_move,
+            castling,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            hash: zobrist::hash_position(&board, side_to_move, castling, en_passant),
+        }
+    }
+
+    pub fn from_setup(position: BoardPosition) -> Self {
+        Self::new(BoardFactory::create(position), PieceColor::White)
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(FenError::MissingFields(fields.len()));
+        }
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidRankCount);
+        }
+        let mut board: BoardGame = [[None; 8]; 8];
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut col = 0;
+            for c in rank.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    col += skip as usize;
+                } else {
+                    if col >= 8 {
+                        return Err(FenError::InvalidRankLength(8 - row));
+                    }
+                    board[row][col] = Some(piece_from_char(c).ok_or(FenError::InvalidPiece(c))?);
+                    col += 1;
+                }
+            }
+            if col != 8 {
+                return Err(FenError::InvalidRankLength(8 - row));
+            }
+        }
+
+        let side_to_move = match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+
+        let mut castling = CastlingRights::NONE;
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                castling.insert(match c {
+                    'K' => CastlingRights::WHITE_KINGSIDE,
+                    'Q' => CastlingRights::WHITE_QUEENSIDE,
+                    'k' => CastlingRights::BLACK_KINGSIDE,
+                    'q' => CastlingRights::BLACK_QUEENSIDE,
+                    _ => return Err(FenError::InvalidCastling(fields[2].to_string())),
+                });
+            }
+        }
+
+        let en_passant = match fields[3] {
+            "-" => None,
+            square => Some(
+                parse_position(square)
+                    .map_err(|_| FenError::InvalidEnPassant(square.to_string()))?,
+            ),
+        };
+        // Keep the square only when a capture is possible, as `play` does,
+        // so identical positions always hash the same.
+        let en_passant = en_passant.filter(|square| {
+            let pawn_row = match side_to_move {
+                PieceColor::White => 1,
+                PieceColor::Black => -1,
+            };
+            let capturer = Some(Piece::new(PieceType::Pawn, side_to_move));
+            [-1, 1].iter().any(|&d_col| {
+                square
+                    .offset(pawn_row, d_col)
+                    .is_some_and(|from| board[from.row()][from.col()] == capturer)
+            })
+        });
+
+        let parse_counter = |field: Option<&&str>, default: u32| match field {
+            None => Ok(default),
+            Some(text) => text
+                .parse::<u32>()
+                .map_err(|_| FenError::InvalidCounter(text.to_string())),
+        };
+        let halfmove_clock = parse_counter(fields.get(4), 0)?;
+        let fullmove_number = parse_counter(fields.get(5), 1)?.max(1);
+
+        Ok(Self::from_parts(
+            board,
+            side_to_move,
+            castling,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        ))
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for (row, cells) in self.board.iter().enumerate() {
+            let mut empty = 0;
+            for cell in cells {
+                match cell {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece_to_char(*piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if row < 7 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.side_to_move {
+            PieceColor::White => " w ",
+            PieceColor::Black => " b ",
+        });
+
+        if self.castling == CastlingRights::NONE {
+            fen.push('-');
+        }
+        for (right, c) in [
+            (CastlingRights::WHITE_KINGSIDE, 'K'),
+            (CastlingRights::WHITE_QUEENSIDE, 'Q'),
+            (CastlingRights::BLACK_KINGSIDE, 'k'),
+            (CastlingRights::BLACK_QUEENSIDE, 'q'),
+        ] {
+            if self.castling.contains(right) {
+                fen.push(c);
+            }
+        }
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&format!(" {}", square)),
+            None => fen.push_str(" -"),
+        }
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+
+    pub fn board(&self) -> &BoardGame {
+        &self.board
+    }
+
+    pub fn side_to_move(&self) -> PieceColor {
+        self.side_to_move
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// Square a pawn can capture onto en passant, only set when an enemy
+    /// pawn actually stands next to the pawn that just moved two squares.
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Plies since the last capture or pawn move, for the fifty-move rule.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.row()][square.col()]
+    }
+
+    pub fn is_in_check(&self, color: PieceColor) -> bool {
+        movegen::is_in_check(&self.board, color)
+    }
+
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        movegen::generate_legal_moves(self)
+    }
+
+    pub fn is_legal(&self, mv: &ChessMove) -> bool {
+        self.legal_moves().contains(mv)
+    }
+
+    /// Matches a move that may lack its promotion piece (as when dropped on
+    /// the board) against the legal moves, defaulting promotions to a queen.
+    pub fn find_legal_move(&self, from: Square, to: Square) -> Option<ChessMove> {
+        let candidates: Vec<ChessMove> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == from && mv.to == to)
+            .collect();
+        candidates
+            .iter()
+            .find(|mv| matches!(mv.promotion, None | Some(PieceType::Queen)))
+            .copied()
+    }
+
+    pub fn is_capture(&self, mv: &ChessMove) -> bool {
+        self.piece_at(mv.to).is_some() || self.is_en_passant(mv)
+    }
+
+    pub fn is_en_passant(&self, mv: &ChessMove) -> bool {
+        Some(mv.to) == self.en_passant
+            && matches!(self.piece_at(mv.from), Some(piece) if piece.piece_type == PieceType::Pawn)
+    }
+
+    pub fn is_castling(&self, mv: &ChessMove) -> bool {
+        matches!(self.piece_at(mv.from), Some(piece) if piece.piece_type == PieceType::King)
+            && mv.from.col().abs_diff(mv.to.col()) == 2
+    }
+
+    /// Square of the piece `mv` takes, which is not `mv.to` en passant.
+    pub fn captured_square(&self, mv: &ChessMove) -> Option<Square> {
+        if self.is_en_passant(mv) {
+            let square = Square::try_from((mv.from.row() as u8, mv.to.col() as u8))
+                .expect("en passant square is on the board");
+            Some(square)
+        } else {
+            self.piece_at(mv.to).map(|_| mv.to)
+        }
+    }
+
+    /// Where the rook starts and ends when `mv` castles.
+    pub fn castling_rook_squares(&self, mv: &ChessMove) -> Option<(Square, Square)> {
+        if !self.is_castling(mv) {
+            return None;
+        }
+        let row = mv.from.row() as u8;
+        let (from, to) = if mv.to.col() > mv.from.col() {
+            (7, 5)
+        } else {
+            (0, 3)
+        };
+        Some((
+            Square::try_from((row, from)).unwrap(),
+            Square::try_from((row, to)).unwrap(),
+        ))
+    }
+
+    /// Plays `mv` without validating it and returns the resulting position.
+    /// Callers are expected to pass moves coming from `legal_moves`.
+    pub fn play(&self, mv: &ChessMove) -> Position {
+        let mut next = *self;
+        let color = self.side_to_move;
+        let piece = next.board[mv.from.row()][mv.from.col()]
+            .take()
+            .expect("no piece on the source square");
+        next.hash ^= zobrist::piece_key(piece, mv.from);
+
+        let captured_square = self.captured_square(mv);
+        if let Some(square) = captured_square
+            && let Some(captured) = next.board[square.row()][square.col()].take()
+        {
+            next.hash ^= zobrist::piece_key(captured, square);
+        }
+
+        let placed = match mv.promotion {
+            Some(piece_type) => Piece::new(piece_type, color),
+            None => piece,
+        };
+        next.board[mv.to.row()][mv.to.col()] = Some(placed);
+        next.hash ^= zobrist::piece_key(placed, mv.to);
+
+        if let Some((rook_from, rook_to)) = self.castling_rook_squares(mv)
+            && let Some(rook) = next.board[rook_from.row()][rook_from.col()].take()
+        {
+            next.board[rook_to.row()][rook_to.col()] = Some(rook);
+            next.hash ^= zobrist::piece_key(rook, rook_from) ^ zobrist::piece_key(rook, rook_to);
+        }
+
+        next.hash ^= zobrist::castling_key(next.castling);
+        for square in [mv.from, mv.to] {
+            next.castling.remove(rights_lost_at(square));
+        }
+        next.hash ^= zobrist::castling_key(next.castling);
+
+        next.hash ^= zobrist::en_passant_key(next.en_passant);
+        next.en_passant = None;
+        if piece.piece_type == PieceType::Pawn && mv.from.row().abs_diff(mv.to.row()) == 2 {
+            let between = Square::try_from((
+                ((mv.from.row() + mv.to.row()) / 2) as u8,
+                mv.from.col() as u8,
+            ))
+            .unwrap();
+            let enemy_pawn = Some(Piece::new(PieceType::Pawn, color.opposite()));
+            let capturable = [-1, 1].iter().any(|&d_col| {
+                mv.to
+                    .offset(0, d_col)
+                    .is_some_and(|side| next.piece_at(side) == enemy_pawn)
+            });
+            if capturable {
+                next.en_passant = Some(between);
+            }
+        }
+        next.hash ^= zobrist::en_passant_key(next.en_passant);
+
+        if piece.piece_type == PieceType::Pawn || captured_square.is_some() {
+            next.halfmove_clock = 0;
+        } else {
+            next.halfmove_clock += 1;
+        }
+        if color == PieceColor::Black {
+            next.fullmove_number += 1;
+        }
+
+        next.side_to_move = color.opposite();
+        next.hash ^= zobrist::side_key();
+        next
+    }
+
+    /// Checks that the position could arise in a game, as far as the engine
+    /// relies on it: one king each, sensible piece counts, no pawn on the
+    /// back ranks, the side that just moved not in check, and castling and
+    /// en passant state that matches the board.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for color in [PieceColor::White, PieceColor::Black] {
+            let pieces: Vec<Piece> = self
+                .board
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|piece| piece.color == color)
+                .copied()
+                .collect();
+            let count = |piece_type| {
+                pieces
+                    .iter()
+                    .filter(|piece| piece.piece_type == piece_type)
+                    .count()
+            };
+            if count(PieceType::King) != 1 {
+                return Err(PositionError::KingCount(color));
+            }
+            if pieces.len() > 16 || count(PieceType::Pawn) > 8 {
+                return Err(PositionError::TooManyPieces(color));
+            }
+        }
+        let back_ranks = [self.board[0], self.board[7]];
+        if back_ranks
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|piece| piece.piece_type == PieceType::Pawn)
+        {
+            return Err(PositionError::PawnOnBackRank);
+        }
+        if self.is_in_check(self.side_to_move.opposite()) {
+            return Err(PositionError::OpponentInCheck);
+        }
+        if !possible_castling(&self.board).contains(self.castling) {
+            return Err(PositionError::InvalidCastling);
+        }
+        if let Some(square) = self.en_passant {
+            // The pawn that just moved two squares stands in front of the
+            // square, seen from its own side, and passed over it.
+            let (pushed_row, d_row) = match self.side_to_move {
+                PieceColor::White => (2, 1),
+                PieceColor::Black => (5, -1),
+            };
+            let pushed = square.offset(d_row, 0).and_then(|pawn| self.piece_at(pawn));
+            let start_empty = square
+                .offset(-d_row, 0)
+                .is_some_and(|start| self.piece_at(start).is_none());
+            if square.row() != pushed_row
+                || self.piece_at(square).is_some()
+                || !start_empty
+                || pushed != Some(Piece::new(PieceType::Pawn, self.side_to_move.opposite()))
+            {
+                return Err(PositionError::InvalidEnPassant(square));
+            }
+        }
+        Ok(())
+    }
+
+    /// Neither side has enough material left to ever deliver mate.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = Vec::new();
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                match cell {
+                    None => {}
+                    Some(piece) => match piece.piece_type {
+                        PieceType::King => {}
+                        PieceType::Bishop | PieceType::Knight => {
+                            minors.push((*piece, (row + col) % 2))
+                        }
+                        _ => return false,
+                    },
+                }
+            }
+        }
+        match minors.as_slice() {
+            [] | [_] => true,
+            // Bishops only, all on the same square colour.
+            all => all
+                .iter()
+                .all(|(piece, shade)| piece.piece_type == PieceType::Bishop && *shade == all[0].1),
+        }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::from_setup(BoardPosition::Standard)
+    }
+}
+
+/// Castling rights that disappear once anything moves from or to `square`.
+fn rights_lost_at(square: Square) -> CastlingRights {
+    match square.to_tuple() {
+        (7, 4) => {
+            CastlingRights(CastlingRights::WHITE_KINGSIDE.0 | CastlingRights::WHITE_QUEENSIDE.0)
+        }
+        (7, 7) => CastlingRights::WHITE_KINGSIDE,
+        (7, 0) => CastlingRights::WHITE_QUEENSIDE,
+        (0, 4) => {
+            CastlingRights(CastlingRights::BLACK_KINGSIDE.0 | CastlingRights::BLACK_QUEENSIDE.0)
+        }
+        (0, 7) => CastlingRights::BLACK_KINGSIDE,
+        (0, 0) => CastlingRights::BLACK_QUEENSIDE,
+        _ => CastlingRights::NONE,
+    }
+}
+
+pub fn piece_from_char(c: char) -> Option<Piece> {
+    let color = if c.is_ascii_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+    let piece_type = match c.to_ascii_lowercase() {
+        'k' => PieceType::King,
+        'q' => PieceType::Queen,
+        'r' => PieceType::Rook,
+        'b' => PieceType::Bishop,
+        'n' => PieceType::Knight,
+        'p' => PieceType::Pawn,
+        _ => return None,
+    };
+    Some(Piece::new(piece_type, color))
+}
+
+pub fn piece_to_char(piece: Piece) -> char {
+    let c = match piece.piece_type {
+        PieceType::King => 'k',
+        PieceType::Queen => 'q',
+        PieceType::Rook => 'r',
+        PieceType::Bishop => 'b',
+        PieceType::Knight => 'n',
+        PieceType::Pawn => 'p',
+    };
+    match piece.color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    fn square(name: &str) -> Square {
+        parse_position(name).unwrap()
+    }
+
+    fn fen(fen: &str) -> Position {
+        Position::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn fen_round_trips() {
+        for text in [
+            STARTING_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 12 40",
+            "8/8/8/8/8/8/8/K6k w - - 0 1",
+        ] {
+            assert_eq!(fen(text).to_fen(), text);
+        }
+        assert_eq!(Position::default().to_fen(), STARTING_FEN);
+        assert_eq!(Position::default().hash(), fen(STARTING_FEN).hash());
+    }
+
+    #[test]
+    fn fen_counters_are_optional_and_en_passant_needs_a_capturer() {
+        assert_eq!(
+            fen("8/8/8/8/8/8/8/K6k b - -").to_fen(),
+            "8/8/8/8/8/8/8/K6k b - - 0 1"
+        );
+        let pushed = fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(pushed.en_passant(), None);
+        assert_eq!(
+            pushed.hash(),
+            Position::default()
+                .play(&parse_move("e2e4").unwrap())
+                .hash()
+        );
+    }
+
+    #[test]
+    fn malformed_fen_is_rejected() {
+        let cases = [
+            ("8/8 w", FenError::MissingFields(2)),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::InvalidRankCount),
+            (
+                "ppppppppp/8/8/8/8/8/8/K6k w - - 0 1",
+                FenError::InvalidRankLength(8),
+            ),
+            (
+                "8/8/8/8/8/8/7/K6k w - - 0 1",
+                FenError::InvalidRankLength(2),
+            ),
+            ("8/8/8/8/8/8/8/K5xk w - - 0 1", FenError::InvalidPiece('x')),
+            (
+                "8/8/8/8/8/8/8/K6k x - - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w KX - 0 1",
+                FenError::InvalidCastling("KX".to_string()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w - e9 0 1",
+                FenError::InvalidEnPassant("e9".to_string()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w - - -1 1",
+                FenError::InvalidCounter("-1".to_string()),
+            ),
+        ];
+        for (text, error) in cases {
+            assert_eq!(Position::from_fen(text).err(), Some(error), "{}", text);
+        }
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_fresh_one() {
+        // Every move from here: castling both ways, en passant, promotions
+        // and captures that take castling rights away.
+        let positions = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ];
+        for text in positions {
+            let position = fen(text);
+            for mv in position.legal_moves() {
+                let child = position.play(&mv);
+                assert_eq!(child.hash(), fen(&child.to_fen()).hash(), "{} {}", text, mv);
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_position_hashes_the_same() {
+        let mut position = Position::default();
+        let start = position.hash();
+        for text in ["g1f3", "g8f6", "f3g1"] {
+            position = position.play(&parse_move(text).unwrap());
+            assert_ne!(position.hash(), start);
+        }
+        position = position.play(&parse_move("f6g8").unwrap());
+        assert_eq!(position.hash(), start);
+        // Same pieces with the other side to move is another position.
+        let moved = Position::default().play(&parse_move("g1f3").unwrap());
+        let back = moved.play(&parse_move("g8f6").unwrap());
+        assert_ne!(back.hash(), start);
+    }
+
+    #[test]
+    fn legal_positions_pass_validation() {
+        for text in [
+            STARTING_FEN,
+            "4k3/8/8/8/8/8/8/4K2R w K - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/8/8/8/8/8/8/K6k b - - 0 1",
+        ] {
+            assert_eq!(fen(text).validate(), Ok(()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn illegal_positions_fail_validation() {
+        use PieceColor::{Black, White};
+        let cases = [
+            (
+                "4k3/8/8/8/8/8/8/8 w - - 0 1",
+                PositionError::KingCount(White),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3kK3 w - - 0 1",
+                PositionError::KingCount(Black),
+            ),
+            (
+                "4k3/8/8/8/8/PPPPPPPP/P7/4K3 w - - 0 1",
+                PositionError::TooManyPieces(White),
+            ),
+            (
+                "4k3/8/NNNNNNNN/NNNNNNNN/8/8/8/4K3 w - - 0 1",
+                PositionError::TooManyPieces(White),
+            ),
+            (
+                "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+                PositionError::PawnOnBackRank,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/p3K3 w - - 0 1",
+                PositionError::PawnOnBackRank,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1",
+                PositionError::OpponentInCheck,
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+                PositionError::InvalidCastling,
+            ),
+            (
+                "4k2r/8/8/8/8/8/8/4K3 w q - 0 1",
+                PositionError::InvalidCastling,
+            ),
+            (
+                "4k3/4p3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+                PositionError::InvalidEnPassant(square("e6")),
+            ),
+        ];
+        for (text, error) in cases {
+            assert_eq!(fen(text).validate(), Err(error), "{}", text);
+        }
+    }
+
+    #[test]
+    fn castling_is_possible_where_king_and_rook_are_home() {
+        assert_eq!(
+            possible_castling(Position::default().board()),
+            CastlingRights::ALL
+        );
+        let mut expected = CastlingRights::WHITE_KINGSIDE;
+        expected.insert(CastlingRights::BLACK_QUEENSIDE);
+        let board = *fen("r3k3/8/8/8/8/8/8/4K2R w - - 0 1").board();
+        assert_eq!(possible_castling(&board), expected);
+        // A king off its square castles nowhere.
+        let board = *fen("r2k3r/8/8/8/8/8/8/R3K2R w - - 0 1").board();
+        assert_eq!(
+            possible_castling(&board),
+            CastlingRights(CastlingRights::WHITE_KINGSIDE.0 | CastlingRights::WHITE_QUEENSIDE.0)
+        );
+        assert_eq!(
+            fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").castling_rights(),
+            CastlingRights::ALL
+        );
+    }
+
+    #[test]
+    fn en_passant_is_possible_behind_a_double_push_with_a_capturer() {
+        let candidates = |text: &str| {
+            let position = fen(text);
+            possible_en_passant(position.board(), position.side_to_move())
+        };
+        assert_eq!(
+            candidates("4k3/8/8/3Pp3/8/8/8/4K3 w - - 0 1"),
+            [square("e6")]
+        );
+        assert_eq!(
+            candidates("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1"),
+            [square("e3")]
+        );
+        assert_eq!(
+            candidates("4k3/8/8/2pPp3/8/8/8/4K3 w - - 0 1"),
+            [square("c6"), square("e6")]
+        );
+        // No capturer, a blocked start square, or the wrong side to move.
+        assert!(candidates("4k3/8/8/4p3/8/8/8/4K3 w - - 0 1").is_empty());
+        assert!(candidates("4k3/4p3/8/3Pp3/8/8/8/4K3 w - - 0 1").is_empty());
+        assert!(candidates("4k3/8/8/3Pp3/8/8/8/4K3 b - - 0 1").is_empty());
+        assert!(candidates(STARTING_FEN).is_empty());
+    }
+
+    #[test]
+    fn custom_positions_with_possible_state_are_legal() {
+        let board = *fen("r3k3/8/8/2pPp3/8/8/8/4K2R w - - 0 1").board();
+        let castling = possible_castling(&board);
+        for en_passant in [None, Some(square("c6")), Some(square("e6"))] {
+            let position =
+                Position::from_parts(board, PieceColor::White, castling, en_passant, 0, 1);
+            assert_eq!(position.validate(), Ok(()), "{}", position.to_fen());
+            assert_eq!(fen(&position.to_fen()).to_fen(), position.to_fen());
+        }
+        let position =
+            Position::from_parts(board, PieceColor::White, castling, Some(square("d6")), 0, 1);
+        assert_eq!(
+            position.validate(),
+            Err(PositionError::InvalidEnPassant(square("d6")))
+        );
+    }
+
+    #[test]
+    fn en_passant_takes_the_pawn_beside() {
+        let position = fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2");
+        let en_passant = parse_move("e5d6").unwrap();
+        assert_eq!(position.captured_square(&en_passant), Some(square("d5")));
+        let after = position.play(&en_passant);
+        assert_eq!(after.piece_at(square("d5")), None);
+        assert_eq!(after.halfmove_clock(), 0);
+
+        let quiet = parse_move("e5e6").unwrap();
+        assert_eq!(position.captured_square(&quiet), None);
+        let capture = fen("4k3/8/3p4/4P3/8/8/8/4K3 w - - 0 2");
+        assert_eq!(
+            capture.captured_square(&parse_move("e5d6").unwrap()),
+            Some(square("d6"))
+        );
+    }
+
+    #[test]
+    fn castling_moves_the_rook() {
+        let position = fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let short = parse_move("e1g1").unwrap();
+        assert_eq!(
+            position.castling_rook_squares(&short),
+            Some((square("h1"), square("f1")))
+        );
+        let long = parse_move("e1c1").unwrap();
+        assert_eq!(
+            position.castling_rook_squares(&long),
+            Some((square("a1"), square("d1")))
+        );
+        let after = position.play(&long);
+        assert_eq!(after.piece_at(square("a1")), None);
+        assert_eq!(
+            after.piece_at(square("d1")),
+            Some(Piece::new(PieceType::Rook, PieceColor::White))
+        );
+
+        let black = fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1");
+        let short = parse_move("e8g8").unwrap();
+        assert_eq!(
+            black.castling_rook_squares(&short),
+            Some((square("h8"), square("f8")))
+        );
+        assert_eq!(
+            position.castling_rook_squares(&parse_move("e1f1").unwrap()),
+            None
+        );
+    }
+}
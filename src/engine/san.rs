@@ -0,0 +1,283 @@
+use crate::engine::{
+    chess_move::{ChessMove, parse_position},
+    error::SanError,
+    piece::PieceType,
+    position::Position,
+};
+
+pub fn piece_letter(piece_type: PieceType) -> Option<char> {
+    match piece_type {
+        PieceType::King => Some('K'),
+        PieceType::Queen => Some('Q'),
+        PieceType::Rook => Some('R'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Knight => Some('N'),
+        PieceType::Pawn => None,
+    }
+}
+
+fn piece_from_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'K' => Some(PieceType::King),
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None,
+    }
+}
+
+/// Standard Algebraic Notation of a legal move in `position`, e.g. `Nbd7`,
+/// `exd5`, `e8=Q+` or `O-O#`.
+pub fn to_san(position: &Position, mv: &ChessMove) -> String {
+    let mut san = String::new();
+    let piece = position
+        .piece_at(mv.from)
+        .expect("move must start on an occupied square");
+
+    if position.is_castling(mv) {
+        san.push_str(if mv.to.col() > mv.from.col() {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let is_capture = position.is_capture(mv);
+        match piece_letter(piece.piece_type) {
+            Some(letter) => {
+                san.push(letter);
+                san.push_str(&disambiguation(position, mv, piece.piece_type));
+            }
+            None => {
+                if is_capture {
+                    san.push((b'a' + mv.from.col() as u8) as char);
+                }
+            }
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(letter) = mv.promotion.and_then(piece_letter) {
+            san.push('=');
+            san.push(letter);
+        }
+    }
+
+    let next = position.play(mv);
+    if next.is_in_check(next.side_to_move()) {
+        san.push(if next.legal_moves().is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    san
+}
+
+/// File, rank or both of the origin square, when another piece of the same
+/// kind could also reach the destination.
+fn disambiguation(position: &Position, mv: &ChessMove, piece_type: PieceType) -> String {
+    let rivals: Vec<ChessMove> = position
+        .legal_moves()
+        .into_iter()
+        .filter(|other| {
+            other.to == mv.to
+                && other.from != mv.from
+                && position
+                    .piece_at(other.from)
+                    .is_some_and(|piece| piece.piece_type == piece_type)
+        })
+        .collect();
+    if rivals.is_empty() {
+        return String::new();
+    }
+
+    let origin = mv.from.to_string();
+    let (file, rank) = origin.split_at(1);
+    if rivals.iter().all(|other| other.from.col() != mv.from.col()) {
+        file.to_string()
+    } else if rivals.iter().all(|other| other.from.row() != mv.from.row()) {
+        rank.to_string()
+    } else {
+        origin
+    }
+}
+
+/// Finds the legal move written as `text` in SAN. Check marks, annotation
+/// glyphs and a missing `x` or `=` are tolerated.
+pub fn parse_san(position: &Position, text: &str) -> Result<ChessMove, SanError> {
+    let cleaned: String = text
+        .trim_end_matches(['+', '#', '!', '?'])
+        .chars()
+        .filter(|&c| c != 'x' && c != '=')
+        .collect();
+    let legal = position.legal_moves();
+
+    let castling_side = match cleaned.as_str() {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    };
+    if let Some(kingside) = castling_side {
+        return legal
+            .into_iter()
+            .find(|mv| position.is_castling(mv) && (mv.to.col() > mv.from.col()) == kingside)
+            .ok_or_else(|| SanError::IllegalMove(text.to_string()));
+    }
+
+    let mut chars: Vec<char> = cleaned.chars().collect();
+    let piece_type = match chars.first().copied().and_then(piece_from_letter) {
+        Some(piece_type) => {
+            chars.remove(0);
+            piece_type
+        }
+        None => PieceType::Pawn,
+    };
+
+    let mut promotion = None;
+    if piece_type == PieceType::Pawn
+        && let Some(&last) = chars.last()
+        && let Some(promoted) = piece_from_letter(last.to_ascii_uppercase())
+        && !last.is_ascii_digit()
+    {
+        promotion = Some(promoted);
+        chars.pop();
+    }
+
+    if chars.len() < 2 {
+        return Err(SanError::InvalidSyntax(text.to_string()));
+    }
+    let destination: String = chars[chars.len() - 2..].iter().collect();
+    let to = parse_position(&destination).map_err(|_| SanError::InvalidSyntax(text.to_string()))?;
+    let mut from_file = None;
+    let mut from_rank = None;
+    for &c in &chars[..chars.len() - 2] {
+        match c {
+            'a'..='h' => from_file = Some(c as usize - 'a' as usize),
+            '1'..='8' => from_rank = Some(8 - (c as usize - '0' as usize)),
+            _ => return Err(SanError::InvalidSyntax(text.to_string())),
+        }
+    }
+
+    let candidates: Vec<ChessMove> = legal
+        .into_iter()
+        .filter(|mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && position
+                    .piece_at(mv.from)
+                    .is_some_and(|piece| piece.piece_type == piece_type)
+                && from_file.is_none_or(|col| mv.from.col() == col)
+                && from_rank.is_none_or(|row| mv.from.row() == row)
+        })
+        .collect();
+    match candidates.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(SanError::IllegalMove(text.to_string())),
+        _ => Err(SanError::AmbiguousMove(text.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    /// Writes `uci` in `fen` as SAN and checks that it reads back.
+    fn san(fen: &str, uci: &str) -> String {
+        let position = Position::from_fen(fen).unwrap();
+        let mv = parse_move(uci).unwrap();
+        assert!(position.is_legal(&mv), "{uci} in {fen}");
+        let text = to_san(&position, &mv);
+        assert_eq!(parse_san(&position, &text), Ok(mv), "{text} in {fen}");
+        text
+    }
+
+    #[test]
+    fn origin_is_added_only_when_needed() {
+        let knights = "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1";
+        assert_eq!(san(knights, "b1d2"), "Nbd2");
+        assert_eq!(san(knights, "f3d2"), "Nfd2");
+        assert_eq!(san(knights, "b1c3"), "Nc3");
+
+        let rooks = "k7/8/8/8/4R3/8/8/4R2K w - - 0 1";
+        assert_eq!(san(rooks, "e1e2"), "R1e2");
+        assert_eq!(san(rooks, "e4e2"), "R4e2");
+        assert_eq!(san(rooks, "e1d1"), "Rd1");
+
+        let queens = "8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(queens, "h4e1"), "Qh4e1");
+        assert_eq!(san(queens, "e4e1"), "Qee1");
+        assert_eq!(san(queens, "h1e1"), "Q1e1");
+    }
+
+    #[test]
+    fn check_and_mate_are_marked() {
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a7"), "Ra7");
+    }
+
+    #[test]
+    fn castling() {
+        let both = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(both, "e1g1"), "O-O");
+        assert_eq!(san(both, "e1c1"), "O-O-O");
+        assert_eq!(san("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"), "O-O+");
+
+        let position = Position::from_fen(both).unwrap();
+        assert_eq!(
+            parse_san(&position, "0-0-0"),
+            Ok(parse_move("e1c1").unwrap())
+        );
+        let without_rights = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1").unwrap();
+        assert_eq!(
+            parse_san(&without_rights, "O-O"),
+            Err(SanError::IllegalMove("O-O".to_string()))
+        );
+    }
+
+    #[test]
+    fn promotion() {
+        let fen = "k2r4/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "e7e8q"), "e8=Q");
+        assert_eq!(san(fen, "e7e8n"), "e8=N");
+        assert_eq!(san(fen, "e7d8q"), "exd8=Q+");
+
+        let position = Position::from_fen(fen).unwrap();
+        assert_eq!(
+            parse_san(&position, "exd8Q"),
+            Ok(parse_move("e7d8q").unwrap())
+        );
+        assert_eq!(
+            parse_san(&position, "e8"),
+            Err(SanError::IllegalMove("e8".to_string()))
+        );
+    }
+
+    #[test]
+    fn unreadable_and_ambiguous_moves_are_rejected() {
+        let position = Position::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(
+            parse_san(&position, "Nd2"),
+            Err(SanError::AmbiguousMove("Nd2".to_string()))
+        );
+        assert_eq!(
+            parse_san(&position, "Nc5"),
+            Err(SanError::IllegalMove("Nc5".to_string()))
+        );
+        assert_eq!(
+            parse_san(&position, "N"),
+            Err(SanError::InvalidSyntax("N".to_string()))
+        );
+        assert_eq!(
+            parse_san(&position, "Nz9"),
+            Err(SanError::InvalidSyntax("Nz9".to_string()))
+        );
+        assert_eq!(
+            parse_san(&position, "Nb1d2+"),
+            Ok(parse_move("b1d2").unwrap())
+        );
+    }
+}
@@ -0,0 +1,689 @@
+use crate::engine::{
+    chess_move::ChessMove,
+    eval::{evaluate, piece_value},
+    piece::PieceType,
+    position::Position,
+    tt::{Bound, TranspositionTable, TtEntry},
+};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const MATE_SCORE: i32 = 30_000;
+pub const MAX_PLY: usize = 128;
+const MAX_DEPTH: u8 = 64;
+const INFINITY: i32 = MATE_SCORE + 1;
+// How many nodes a thread searches between two looks at the clock and the
+// shared node counter.
+const CHECK_INTERVAL: u64 = 1024;
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+/// When to stop searching. Unset fields are unlimited; with every field
+/// unset the search runs until `stop` is signalled.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+    /// Only consider these root moves; empty means all legal moves.
+    pub search_moves: Vec<ChessMove>,
+    /// Ends the search early. Each search should get a fresh signal, made
+    /// before the search is handed to another thread, so that a stop sent
+    /// at any time after that is never missed.
+    pub stop: StopSignal,
+}
+
+/// One candidate line of a MultiPV search.
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: i32,
+    pub pv: Vec<ChessMove>,
+}
+
+/// Outcome of a search. `best_move`, `score` and `pv` describe the top line;
+/// `lines` holds every requested line ranked best first.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<ChessMove>,
+    pub score: i32,
+    pub depth: u8,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub pv: Vec<ChessMove>,
+    pub lines: Vec<PvLine>,
+}
+
+impl SearchResult {
+    /// Moves until mate (negative when getting mated), if the score is one.
+    pub fn mate_in(score: i32) -> Option<i32> {
+        if score >= MATE_SCORE - MAX_PLY as i32 {
+            Some((MATE_SCORE - score + 1) / 2)
+        } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+            Some(-(MATE_SCORE + score) / 2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Thinking time for one move out of `remaining` on the clock, aiming to
+/// spread it over `moves_to_go` moves (or a typical game length when the
+/// control has no move count) and spending most of the increment.
+pub fn allocate_time(
+    remaining: Duration,
+    increment: Duration,
+    moves_to_go: Option<u32>,
+) -> Duration {
+    const SAFETY_MARGIN: Duration = Duration::from_millis(50);
+    let moves = moves_to_go.unwrap_or(30).clamp(1, 50);
+    let budget = remaining / moves + increment * 3 / 4;
+    let ceiling = remaining.saturating_sub(SAFETY_MARGIN).max(remaining / 2);
+    budget.min(ceiling).max(Duration::from_millis(1))
+}
+
+/// Cloneable handle used to interrupt a running search from another thread.
+/// Once stopped it stays stopped.
+#[derive(Clone, Debug, Default)]
+pub struct StopSignal(Arc<AtomicBool>);
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Lazy SMP searcher: every thread runs its own iterative deepening over the
+/// same position and they cooperate only through the shared transposition
+/// table. With a single thread and no time limit the search is fully
+/// deterministic.
+pub struct Searcher {
+    tt: TranspositionTable,
+    threads: usize,
+    multi_pv: usize,
+}
+
+struct Shared<'a> {
+    tt: &'a TranspositionTable,
+    // Set when a limit is reached or the main thread is done; the caller's
+    // own signal is in `limits`.
+    finished: AtomicBool,
+    limits: &'a SearchLimits,
+    multi_pv: usize,
+    start: Instant,
+    nodes: AtomicU64,
+}
+
+impl Searcher {
+    pub fn new(threads: usize, hash_mb: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(hash_mb),
+            threads: threads.max(1),
+            multi_pv: 1,
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    /// Number of best root moves to report, each with its own line.
+    pub fn set_multi_pv(&mut self, lines: usize) {
+        self.multi_pv = lines.max(1);
+    }
+
+    pub fn resize_hash(&mut self, hash_mb: usize) {
+        self.tt = TranspositionTable::new(hash_mb);
+    }
+
+    /// Forgets everything learned from previous searches, e.g. for a new game.
+    pub fn clear(&self) {
+        self.tt.clear();
+    }
+
+    /// Searches `position`. `history` holds the hashes of the positions that
+    /// led to it, oldest first, so repetitions can be scored as draws.
+    pub fn search(
+        &self,
+        position: &Position,
+        history: &[u64],
+        limits: &SearchLimits,
+    ) -> SearchResult {
+        self.search_with_progress(position, history, limits, &mut |_| {})
+    }
+
+    /// Like `search`, calling `on_iteration` with the current result every
+    /// time the main thread completes a depth.
+    pub fn search_with_progress(
+        &self,
+        position: &Position,
+        history: &[u64],
+        limits: &SearchLimits,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        let shared = Shared {
+            tt: &self.tt,
+            finished: AtomicBool::new(false),
+            limits,
+            multi_pv: self.multi_pv,
+            start: Instant::now(),
+            nodes: AtomicU64::new(0),
+        };
+
+        let result = thread::scope(|scope| {
+            for id in 1..self.threads {
+                let shared = &shared;
+                scope.spawn(move || {
+                    Worker::new(shared, id, history).iterative_deepening(position, &mut |_| {})
+                });
+            }
+            let result =
+                Worker::new(&shared, 0, history).iterative_deepening(position, on_iteration);
+            // Helpers never finish on their own without a limit; the main
+            // thread decides when the search is over.
+            shared.finished.store(true, Ordering::Relaxed);
+            result
+        });
+
+        SearchResult {
+            nodes: shared.nodes.load(Ordering::Relaxed),
+            elapsed: shared.start.elapsed(),
+            ..result
+        }
+    }
+}
+
+impl Shared<'_> {
+    fn is_stopped(&self) -> bool {
+        self.finished.load(Ordering::Relaxed) || self.limits.stop.is_stopped()
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(1, DEFAULT_HASH_MB)
+    }
+}
+
+struct Worker<'a> {
+    shared: &'a Shared<'a>,
+    id: usize,
+    local_nodes: u64,
+    killers: Vec<[Option<ChessMove>; 2]>,
+    pv: Vec<Vec<ChessMove>>,
+    hash_stack: Vec<u64>,
+    excluded_root_moves: Vec<ChessMove>,
+}
+
+impl<'a> Worker<'a> {
+    fn new(shared: &'a Shared<'a>, id: usize, history: &[u64]) -> Self {
+        Self {
+            shared,
+            id,
+            local_nodes: 0,
+            killers: vec![[None; 2]; MAX_PLY + 1],
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            hash_stack: history.to_vec(),
+            excluded_root_moves: Vec::new(),
+        }
+    }
+
+    fn iterative_deepening(
+        &mut self,
+        position: &Position,
+        on_iteration: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        let mut root_moves = position.legal_moves();
+        let search_moves = &self.shared.limits.search_moves;
+        if !search_moves.is_empty() {
+            root_moves.retain(|mv| search_moves.contains(mv));
+        }
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            pv: root_moves.first().copied().into_iter().collect(),
+            lines: Vec::new(),
+        };
+        if root_moves.is_empty() {
+            return result;
+        }
+
+        // Helpers only exist to fill the table, one line is enough for them.
+        let line_count = if self.id == 0 {
+            self.shared.multi_pv.min(root_moves.len())
+        } else {
+            1
+        };
+        let max_depth = self.shared.limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+        let mut depth = 1;
+        while depth <= max_depth {
+            // Odd helpers run one ply ahead so threads spread across depths.
+            let search_depth = if self.id % 2 == 1 {
+                (depth + 1).min(max_depth)
+            } else {
+                depth
+            };
+
+            let lines = self.search_lines(position, search_depth, line_count);
+            if self.shared.is_stopped() && result.depth > 0 {
+                break;
+            }
+            if let Some(best) = lines.first() {
+                result.best_move = best.pv.first().copied().or(result.best_move);
+                result.score = best.score;
+                if !best.pv.is_empty() {
+                    result.pv = best.pv.clone();
+                }
+                result.depth = search_depth;
+                result.lines = lines;
+                self.flush_nodes();
+                result.nodes = self.shared.nodes.load(Ordering::Relaxed);
+                result.elapsed = self.shared.start.elapsed();
+                on_iteration(&result);
+            }
+            let is_mate = result.score.abs() >= MATE_SCORE - MAX_PLY as i32;
+            if self.shared.is_stopped() || (is_mate && line_count == 1) {
+                break;
+            }
+            depth += 1;
+        }
+
+        self.flush_nodes();
+        result
+    }
+
+    /// Searches the root once per requested line, each time excluding the
+    /// root moves already reported, and returns the lines ranked best first.
+    fn search_lines(&mut self, position: &Position, depth: u8, line_count: usize) -> Vec<PvLine> {
+        self.excluded_root_moves.clear();
+        let mut lines: Vec<PvLine> = Vec::with_capacity(line_count);
+        for _ in 0..line_count {
+            let score = self.negamax(position, depth as i32, 0, -INFINITY, INFINITY);
+            if self.shared.is_stopped() {
+                break;
+            }
+            let Some(&first) = self.pv[0].first() else {
+                break;
+            };
+            self.excluded_root_moves.push(first);
+            lines.push(PvLine {
+                score,
+                pv: self.pv[0].clone(),
+            });
+        }
+        self.excluded_root_moves.clear();
+        lines.sort_by_key(|line| -line.score);
+        lines
+    }
+
+    fn flush_nodes(&mut self) {
+        self.shared
+            .nodes
+            .fetch_add(self.local_nodes, Ordering::Relaxed);
+        self.local_nodes = 0;
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.local_nodes >= CHECK_INTERVAL {
+            self.flush_nodes();
+            let limits = self.shared.limits;
+            let out_of_nodes = limits
+                .nodes
+                .is_some_and(|max| self.shared.nodes.load(Ordering::Relaxed) >= max);
+            let out_of_time = limits
+                .movetime
+                .is_some_and(|max| self.shared.start.elapsed() >= max);
+            if out_of_nodes || out_of_time {
+                self.shared.finished.store(true, Ordering::Relaxed);
+            }
+        }
+        self.shared.is_stopped()
+    }
+
+    fn is_repetition(&self, hash: u64) -> bool {
+        self.hash_stack
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .any(|&previous| previous == hash)
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.pv[ply].clear();
+        if self.should_stop() {
+            return 0;
+        }
+        self.local_nodes += 1;
+
+        let hash = position.hash();
+        if ply > 0
+            && (self.is_repetition(hash)
+                || position.halfmove_clock() >= 100
+                || position.is_insufficient_material())
+        {
+            return 0;
+        }
+        if ply >= MAX_PLY {
+            return evaluate(position);
+        }
+
+        let in_check = position.is_in_check(position.side_to_move());
+        if in_check {
+            depth += 1;
+        }
+        if depth <= 0 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        let tt_entry = self.shared.tt.probe(hash);
+        let tt_move = tt_entry.and_then(|entry| entry.best_move);
+        let is_pv_node = beta - alpha > 1;
+        if let Some(entry) = tt_entry {
+            // PV nodes always search so the principal variation stays whole.
+            if !is_pv_node && entry.depth as i32 >= depth {
+                let score = score_from_tt(entry.score, ply);
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if cutoff {
+                    return score;
+                }
+            }
+        }
+
+        let mut moves = position.legal_moves();
+        let search_moves = &self.shared.limits.search_moves;
+        let in_multi_pv = ply == 0 && !self.excluded_root_moves.is_empty();
+        let restricted_root = ply == 0 && (in_multi_pv || !search_moves.is_empty());
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        if restricted_root {
+            moves.retain(|mv| {
+                !self.excluded_root_moves.contains(mv)
+                    && (search_moves.is_empty() || search_moves.contains(mv))
+            });
+        }
+        self.order_moves(position, &mut moves, tt_move, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        self.hash_stack.push(hash);
+
+        for (index, mv) in moves.iter().enumerate() {
+            let child = position.play(mv);
+            let score = if index == 0 {
+                -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)
+            } else {
+                // Principal variation search: prove the move is no better
+                // with a null window before paying for a full re-search.
+                let probe = -self.negamax(&child, depth - 1, ply + 1, -alpha - 1, -alpha);
+                if probe > alpha && probe < beta {
+                    -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)
+                } else {
+                    probe
+                }
+            };
+
+            if self.shared.is_stopped() {
+                self.hash_stack.pop();
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(*mv);
+                if score > alpha {
+                    alpha = score;
+                    let (head, tail) = self.pv.split_at_mut(ply + 1);
+                    head[ply].clear();
+                    head[ply].push(*mv);
+                    head[ply].extend_from_slice(&tail[0]);
+                }
+            }
+
+            if alpha >= beta {
+                if !position.is_capture(mv) && mv.promotion.is_none() {
+                    let killers = &mut self.killers[ply];
+                    if killers[0] != Some(*mv) {
+                        killers[1] = killers[0];
+                        killers[0] = Some(*mv);
+                    }
+                }
+                break;
+            }
+        }
+        self.hash_stack.pop();
+
+        // A root searched with some moves excluded has no true value to keep.
+        if !restricted_root {
+            let bound = if best_score >= beta {
+                Bound::Lower
+            } else if best_score > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.shared.tt.store(
+                hash,
+                TtEntry {
+                    best_move,
+                    score: score_to_tt(best_score, ply),
+                    depth: depth as u8,
+                    bound,
+                },
+            );
+        }
+
+        best_score
+    }
+
+    fn quiescence(&mut self, position: &Position, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.local_nodes += 1;
+
+        let stand_pat = evaluate(position);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures: Vec<ChessMove> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| position.is_capture(mv) || mv.promotion.is_some())
+            .collect();
+        self.order_moves(position, &mut captures, None, ply);
+
+        for mv in &captures {
+            let score = -self.quiescence(&position.play(mv), ply + 1, -beta, -alpha);
+            if self.shared.is_stopped() {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn order_moves(
+        &self,
+        position: &Position,
+        moves: &mut [ChessMove],
+        tt_move: Option<ChessMove>,
+        ply: usize,
+    ) {
+        let killers = self.killers[ply];
+        moves.sort_by_cached_key(|mv| {
+            let score = if Some(*mv) == tt_move {
+                1_000_000
+            } else if position.is_capture(mv) || mv.promotion.is_some() {
+                // Most valuable victim, least valuable attacker.
+                let victim = match position.piece_at(mv.to) {
+                    Some(piece) => piece_value(piece.piece_type),
+                    None if position.is_en_passant(mv) => piece_value(PieceType::Pawn),
+                    None => 0,
+                };
+                let attacker = position
+                    .piece_at(mv.from)
+                    .map_or(0, |piece| piece_value(piece.piece_type));
+                let promotion = mv.promotion.map_or(0, piece_value);
+                100_000 + (victim + promotion) * 10 - attacker / 10
+            } else if killers[0] == Some(*mv) {
+                90_000
+            } else if killers[1] == Some(*mv) {
+                80_000
+            } else {
+                0
+            };
+            -score
+        });
+    }
+}
+
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    fn position(fen: &str) -> Position {
+        Position::from_fen(fen).unwrap()
+    }
+
+    fn depth(depth: u8) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    #[test]
+    fn single_thread_search_is_deterministic() {
+        let start = Position::default();
+        let first = Searcher::new(1, 1).search(&start, &[], &depth(4));
+        let second = Searcher::new(1, 1).search(&start, &[], &depth(4));
+        let pv: Vec<String> = first.pv.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(first.depth, 4);
+        assert_eq!(first.score, 0);
+        assert_eq!(pv, ["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(first.best_move, second.best_move);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.pv, second.pv);
+        assert_eq!(first.nodes, second.nodes);
+    }
+
+    #[test]
+    fn fixed_depth_search_wins_the_hanging_queen() {
+        let result = Searcher::new(1, 1).search(
+            &position("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1"),
+            &[],
+            &depth(3),
+        );
+        assert_eq!(result.best_move, Some(parse_move("d1d5").unwrap()));
+        assert!(result.score > 500, "score {}", result.score);
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let result = Searcher::new(1, 1).search(
+            &position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1"),
+            &[],
+            &depth(3),
+        );
+        assert_eq!(result.best_move, Some(parse_move("a1a8").unwrap()));
+        assert_eq!(SearchResult::mate_in(result.score), Some(1));
+    }
+
+    #[test]
+    fn multi_pv_ranks_distinct_root_moves() {
+        let mut searcher = Searcher::new(1, 1);
+        searcher.set_multi_pv(3);
+        let result = searcher.search(&Position::default(), &[], &depth(3));
+        assert_eq!(result.lines.len(), 3);
+        assert!(
+            result
+                .lines
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score)
+        );
+        assert_ne!(result.lines[0].pv[0], result.lines[1].pv[0]);
+        assert_ne!(result.lines[1].pv[0], result.lines[2].pv[0]);
+    }
+
+    #[test]
+    fn stop_sent_before_the_search_starts_is_kept() {
+        let limits = SearchLimits::default();
+        limits.stop.stop();
+        let start = Position::default();
+        let result = Searcher::new(2, 1).search(&start, &[], &limits);
+        assert_eq!(result.depth, 0);
+        assert!(result.best_move.is_some_and(|mv| start.is_legal(&mv)));
+    }
+
+    #[test]
+    fn limits_can_be_reused_after_a_search_ends() {
+        let searcher = Searcher::new(2, 1);
+        let limits = depth(2);
+        searcher.search(&Position::default(), &[], &limits);
+        let result = searcher.search(&Position::default(), &[], &limits);
+        assert_eq!(result.depth, 2);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::engine::{
+    chess_move::ChessMove,
+    search::{SearchLimits, SearchResult},
+};
+use rand::Rng;
+
+pub const MAX_SKILL_LEVEL: u8 = 20;
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = 2200;
+// Lines compared when a weakened engine picks its move.
+const CANDIDATE_LINES: usize = 4;
+const PAWN_VALUE: i32 = 100;
+
+/// Playing strength of the computer opponent, from 0 (beginner) to
+/// `MAX_SKILL_LEVEL` (full strength).
+///
+/// Below full strength the search is cut short and the move is picked among
+/// the best `CANDIDATE_LINES` lines, with a random bonus that grows as the
+/// level drops, so weaker levels play a plausible but worse move more often.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Skill {
+    level: u8,
+}
+
+impl Skill {
+    pub fn from_level(level: u8) -> Self {
+        Self {
+            level: level.min(MAX_SKILL_LEVEL),
+        }
+    }
+
+    pub fn full_strength() -> Self {
+        Self::from_level(MAX_SKILL_LEVEL)
+    }
+
+    /// Closest level for a target rating. The scale is approximate: levels
+    /// are spread linearly between `MIN_ELO` and `MAX_ELO`.
+    pub fn from_elo(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        let span = MAX_ELO - MIN_ELO;
+        let level = ((elo - MIN_ELO) * MAX_SKILL_LEVEL as u32 + span / 2) / span;
+        Self::from_level(level as u8)
+    }
+
+    pub fn level(self) -> u8 {
+        self.level
+    }
+
+    pub fn approximate_elo(self) -> u32 {
+        MIN_ELO + (MAX_ELO - MIN_ELO) * self.level as u32 / MAX_SKILL_LEVEL as u32
+    }
+
+    pub fn is_full_strength(self) -> bool {
+        self.level == MAX_SKILL_LEVEL
+    }
+
+    /// Number of lines the search must report for `pick_move` to choose from.
+    pub fn multi_pv(self) -> usize {
+        if self.is_full_strength() {
+            1
+        } else {
+            CANDIDATE_LINES
+        }
+    }
+
+    /// Narrows `limits` to the depth and node budget allowed at this level.
+    pub fn restrict(self, limits: &SearchLimits) -> SearchLimits {
+        if self.is_full_strength() {
+            return limits.clone();
+        }
+        let max_depth = 1 + self.level / 2;
+        let max_nodes = 500u64 << (self.level / 2);
+        SearchLimits {
+            depth: Some(limits.depth.map_or(max_depth, |depth| depth.min(max_depth))),
+            nodes: Some(limits.nodes.map_or(max_nodes, |nodes| nodes.min(max_nodes))),
+            ..limits.clone()
+        }
+    }
+
+    /// Chooses among the ranked lines of `result`. Each line gets a random
+    /// bonus proportional to how far it trails the best one, scaled by the
+    /// weakness of the level; the highest adjusted score wins.
+    pub fn pick_move(self, result: &SearchResult, rng: &mut impl Rng) -> Option<ChessMove> {
+        if self.is_full_strength() || result.lines.len() < 2 {
+            return result.best_move;
+        }
+
+        let weakness = 120 - 2 * self.level as i32;
+        let top_score = result.lines[0].score;
+        let last_score = result.lines[result.lines.len() - 1].score;
+        let delta = (top_score - last_score).min(PAWN_VALUE);
+
+        let mut best_adjusted = i32::MIN;
+        let mut chosen = result.best_move;
+        for line in &result.lines {
+            let push =
+                (weakness * (top_score - line.score) + delta * rng.random_range(0..weakness)) / 128;
+            if line.score + push >= best_adjusted {
+                best_adjusted = line.score + push;
+                chosen = line.pv.first().copied().or(chosen);
+            }
+        }
+        chosen
+    }
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self::full_strength()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{board::BoardPosition, position::Position, search::Searcher};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// Ranked candidate lines for the start position, as a weakened player
+    /// would ask for them.
+    fn start_lines() -> SearchResult {
+        let mut searcher = Searcher::new(1, 1);
+        searcher.set_multi_pv(CANDIDATE_LINES);
+        let limits = SearchLimits {
+            depth: Some(2),
+            ..SearchLimits::default()
+        };
+        searcher.search(&Position::from_setup(BoardPosition::Standard), &[], &limits)
+    }
+
+    #[test]
+    fn full_strength_always_plays_the_best_line() {
+        let skill = Skill::from_level(MAX_SKILL_LEVEL);
+        assert!(skill.is_full_strength());
+        assert_eq!(skill.multi_pv(), 1);
+        let result = start_lines();
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            assert_eq!(skill.pick_move(&result, &mut rng), result.best_move);
+        }
+    }
+
+    #[test]
+    fn levels_and_ratings_are_monotonic_and_clamped() {
+        assert_eq!(Skill::from_level(u8::MAX).level(), MAX_SKILL_LEVEL);
+        assert_eq!(Skill::from_elo(0).level(), 0);
+        assert_eq!(Skill::from_elo(MIN_ELO).level(), 0);
+        assert_eq!(Skill::from_elo(MAX_ELO).level(), MAX_SKILL_LEVEL);
+        assert_eq!(Skill::from_elo(u32::MAX).level(), MAX_SKILL_LEVEL);
+        assert_eq!(Skill::from_level(0).approximate_elo(), MIN_ELO);
+        assert_eq!(Skill::full_strength().approximate_elo(), MAX_ELO);
+
+        for level in 0..=MAX_SKILL_LEVEL {
+            let skill = Skill::from_level(level);
+            assert_eq!(Skill::from_elo(skill.approximate_elo()), skill);
+            if level > 0 {
+                let weaker = Skill::from_level(level - 1);
+                assert!(weaker.approximate_elo() < skill.approximate_elo());
+            }
+        }
+        let mut previous = 0;
+        for elo in (0..3000).step_by(25) {
+            let level = Skill::from_elo(elo).level();
+            assert!(level >= previous, "{} Elo", elo);
+            previous = level;
+        }
+    }
+
+    #[test]
+    fn weak_levels_pick_among_the_candidate_lines() {
+        let result = start_lines();
+        assert_eq!(result.lines.len(), CANDIDATE_LINES);
+        let candidates: Vec<ChessMove> = result.lines.iter().map(|line| line.pv[0]).collect();
+        let legal = Position::from_setup(BoardPosition::Standard).legal_moves();
+        let skill = Skill::from_level(0);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut other_than_best = 0;
+        for _ in 0..200 {
+            let chosen = skill.pick_move(&result, &mut rng).unwrap();
+            assert!(candidates.contains(&chosen) && legal.contains(&chosen));
+            if Some(chosen) != result.best_move {
+                other_than_best += 1;
+            }
+        }
+        assert!(other_than_best > 0);
+    }
+
+    #[test]
+    fn weak_levels_search_less() {
+        let stop = SearchLimits::default();
+        let restricted = Skill::from_level(4).restrict(&SearchLimits {
+            depth: Some(10),
+            ..stop.clone()
+        });
+        assert_eq!(restricted.depth, Some(3));
+        assert_eq!(restricted.nodes, Some(2000));
+        stop.stop.stop();
+        assert!(restricted.stop.is_stopped());
+
+        let full = Skill::full_strength().restrict(&SearchLimits::default());
+        assert_eq!((full.depth, full.nodes), (None, None));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::engine::{
+    chess_move::{ChessMove, Square},
+    piece::PieceType,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TtEntry {
+    pub best_move: Option<ChessMove>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+/// One slot of the table. The key is stored XOR-ed with the data so a torn
+/// write from another thread simply fails verification on probe instead of
+/// returning a mix of two entries; no lock is ever taken.
+struct Slot {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Transposition table shared by every search thread.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+const SLOT_BYTES: usize = std::mem::size_of::<Slot>();
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let count = ((size_mb.max(1) * 1024 * 1024) / SLOT_BYTES).next_power_of_two() / 2;
+        let slots = (0..count.max(1))
+            .map(|_| Slot {
+                key_xor_data: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            })
+            .collect();
+        Self { slots }
+    }
+
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key_xor_data.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[(key as usize) & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let check = slot.key_xor_data.load(Ordering::Relaxed);
+        if data == 0 || check ^ data != key {
+            return None;
+        }
+        Some(unpack(data))
+    }
+
+    pub fn store(&self, key: u64, entry: TtEntry) {
+        let slot = self.slot(key);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.key_xor_data.load(Ordering::Relaxed) ^ old_data;
+        if old_key == key && old_data != 0 && unpack(old_data).depth > entry.depth {
+            return;
+        }
+        let data = pack(entry);
+        slot.key_xor_data.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+fn encode_move(mv: Option<ChessMove>) -> u64 {
+    match mv {
+        Some(mv) => {
+            let promotion = match mv.promotion {
+                Some(PieceType::Queen) => 1,
+                Some(PieceType::Rook) => 2,
+                Some(PieceType::Bishop) => 3,
+                Some(PieceType::Knight) => 4,
+                _ => 0,
+            };
+            (mv.from.index() as u64) | ((mv.to.index() as u64) << 6) | (promotion << 12)
+        }
+        None => 0,
+    }
+}
+
+fn decode_move(bits: u64) -> Option<ChessMove> {
+    let from = (bits & 0x3F) as u8;
+    let to = ((bits >> 6) & 0x3F) as u8;
+    if from == to {
+        return None;
+    }
+    let promotion = match (bits >> 12) & 0x7 {
+        1 => Some(PieceType::Queen),
+        2 => Some(PieceType::Rook),
+        3 => Some(PieceType::Bishop),
+        4 => Some(PieceType::Knight),
+        _ => None,
+    };
+    Some(ChessMove {
+        from: Square::try_from(from).ok()?,
+        to: Square::try_from(to).ok()?,
+        promotion,
+    })
+}
+
+fn pack(entry: TtEntry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    encode_move(entry.best_move)
+        | ((entry.score as i16 as u16 as u64) << 16)
+        | ((entry.depth as u64) << 32)
+        | (bound << 40)
+}
+
+fn unpack(data: u64) -> TtEntry {
+    let bound = match (data >> 40) & 0x3 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    TtEntry {
+        best_move: decode_move(data & 0xFFFF),
+        score: ((data >> 16) & 0xFFFF) as u16 as i16 as i32,
+        depth: ((data >> 32) & 0xFF) as u8,
+        bound,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    #[test]
+    fn store_and_probe_round_trip() {
+        let table = TranspositionTable::new(1);
+        let key = 0x1234_5678_9abc_def0;
+        let entry = TtEntry {
+            best_move: Some(parse_move("e7e8n").unwrap()),
+            score: -29_990,
+            depth: 12,
+            bound: Bound::Lower,
+        };
+        table.store(key, entry);
+        let found = table.probe(key).unwrap();
+        assert_eq!(found.best_move, entry.best_move);
+        assert_eq!(found.score, entry.score);
+        assert_eq!(found.depth, entry.depth);
+        assert_eq!(found.bound, entry.bound);
+    }
+
+    #[test]
+    fn probe_misses_other_keys_and_cleared_entries() {
+        let table = TranspositionTable::new(1);
+        let entry = TtEntry {
+            best_move: None,
+            score: 35,
+            depth: 3,
+            bound: Bound::Exact,
+        };
+        table.store(42, entry);
+        assert!(table.probe(43).is_none());
+        assert!(
+            table
+                .probe(42)
+                .is_some_and(|found| found.best_move.is_none())
+        );
+        table.clear();
+        assert!(table.probe(42).is_none());
+    }
+
+    #[test]
+    fn deeper_entry_is_kept_over_shallower_one() {
+        let table = TranspositionTable::new(1);
+        let entry = |depth| TtEntry {
+            best_move: None,
+            score: depth as i32,
+            depth,
+            bound: Bound::Exact,
+        };
+        table.store(7, entry(8));
+        table.store(7, entry(2));
+        assert_eq!(table.probe(7).unwrap().depth, 8);
+        table.store(7, entry(9));
+        assert_eq!(table.probe(7).unwrap().depth, 9);
+    }
+}
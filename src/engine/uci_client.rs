@@ -0,0 +1,403 @@
+use crate::engine::{
+    chess_move::{ChessMove, parse_move},
+    error::PlayerError,
+    piece::PieceColor,
+    player::{MoveRequest, Player},
+    position::{Position, STARTING_FEN},
+    search::{MATE_SCORE, SearchLimits, StopSignal},
+};
+use crate::game::Game;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the engine gets to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Extra time allowed on top of the move budget before giving up.
+const MOVE_GRACE: Duration = Duration::from_secs(5);
+/// Upper bound for searches limited by depth or nodes only.
+const DEFAULT_MOVE_TIMEOUT: Duration = Duration::from_secs(120);
+/// How often a wait for `bestmove` looks at the request's stop signal.
+const STOP_POLL: Duration = Duration::from_millis(20);
+
+/// Latest `info` line seen during a search.
+#[derive(Clone, Debug, Default)]
+pub struct EngineInfo {
+    pub depth: u8,
+    /// Centipawns from the engine's point of view, mates mapped onto the
+    /// same scale as `SearchResult::score`.
+    pub score: Option<i32>,
+    pub nodes: u64,
+    pub pv: Vec<ChessMove>,
+}
+
+/// A UCI engine running as a child process, usable as a `Player`.
+pub struct UciClient {
+    path: PathBuf,
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    move_timeout: Duration,
+    last_info: Option<EngineInfo>,
+}
+
+impl UciClient {
+    /// Starts the engine at `path` and performs the `uci`/`isready`
+    /// handshake.
+    pub fn spawn(path: impl AsRef<Path>, args: &[String]) -> Result<Self, PlayerError> {
+        let path = path.as_ref().to_path_buf();
+        let mut child = Command::new(&path)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| PlayerError::Spawn(format!("{}: {}", path.display(), e)))?;
+
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            path,
+            child,
+            stdin,
+            lines,
+            move_timeout: DEFAULT_MOVE_TIMEOUT,
+            last_info: None,
+        };
+
+        client.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = client.read_line(deadline, HANDSHAKE_TIMEOUT)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                client.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        client.is_ready()?;
+        Ok(client)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Longest wait for a move searched without a time limit.
+    pub fn set_move_timeout(&mut self, timeout: Duration) {
+        self.move_timeout = timeout;
+    }
+
+    pub fn last_info(&self) -> Option<&EngineInfo> {
+        self.last_info.as_ref()
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), PlayerError> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.is_ready()
+    }
+
+    pub fn is_ready(&mut self) -> Result<(), PlayerError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.read_line(deadline, HANDSHAKE_TIMEOUT)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), PlayerError> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|_| PlayerError::Crashed)
+    }
+
+    fn read_line(&mut self, deadline: Instant, timeout: Duration) -> Result<String, PlayerError> {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(wait) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(PlayerError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(PlayerError::Crashed),
+        }
+    }
+
+    fn position_command(game: &Game) -> String {
+        let start = game.start_position().to_fen();
+        let mut command = if start == STARTING_FEN {
+            "position startpos".to_string()
+        } else {
+            format!("position fen {}", start)
+        };
+        if !game.moves().is_empty() {
+            command.push_str(" moves");
+            for mv in game.moves() {
+                command.push_str(&format!(" {}", mv));
+            }
+        }
+        command
+    }
+
+    fn go_command(request: &MoveRequest) -> String {
+        let mut command = "go".to_string();
+        let SearchLimits {
+            depth,
+            nodes,
+            movetime,
+            search_moves,
+            // Passed on as a `stop` command while waiting for the move.
+            stop: _,
+        } = &request.limits;
+        if let Some(clock) = request.clock {
+            command.push_str(&format!(
+                " wtime {} btime {} winc {} binc {}",
+                clock.white.as_millis(),
+                clock.black.as_millis(),
+                clock.white_increment.as_millis(),
+                clock.black_increment.as_millis()
+            ));
+            if let Some(moves) = clock.moves_to_go {
+                command.push_str(&format!(" movestogo {}", moves));
+            }
+        }
+        if let Some(depth) = depth {
+            command.push_str(&format!(" depth {}", depth));
+        }
+        if let Some(nodes) = nodes {
+            command.push_str(&format!(" nodes {}", nodes));
+        }
+        if let Some(movetime) = movetime {
+            command.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        if !search_moves.is_empty() {
+            command.push_str(" searchmoves");
+            for mv in search_moves {
+                command.push_str(&format!(" {}", mv));
+            }
+        }
+        if command == "go" {
+            command.push_str(" infinite");
+        }
+        command
+    }
+
+    /// Time after which the engine is told to stop, then given up on.
+    fn move_deadline(&self, request: &MoveRequest, side: PieceColor) -> Duration {
+        let budget = request
+            .limits
+            .movetime
+            .or(request.clock.map(|clock| clock.remaining(side)));
+        match budget {
+            Some(budget) => budget + MOVE_GRACE,
+            None => self.move_timeout,
+        }
+    }
+
+    fn parse_info(line: &str, position: &Position) -> Option<EngineInfo> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let mut info = EngineInfo::default();
+        let mut has_pv = false;
+        let mut i = 1;
+        while i < tokens.len() {
+            match tokens[i] {
+                "depth" => info.depth = tokens.get(i + 1)?.parse().ok()?,
+                "nodes" => info.nodes = tokens.get(i + 1)?.parse().ok()?,
+                "score" => {
+                    let value: i32 = tokens.get(i + 2)?.parse().ok()?;
+                    info.score = match *tokens.get(i + 1)? {
+                        "cp" => Some(value),
+                        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+                        "mate" => Some(-MATE_SCORE - 2 * value),
+                        _ => None,
+                    };
+                    i += 1;
+                }
+                "pv" => {
+                    has_pv = true;
+                    let mut current = *position;
+                    for text in &tokens[i + 1..] {
+                        match parse_move(text) {
+                            Ok(mv) if current.is_legal(&mv) => {
+                                current = current.play(&mv);
+                                info.pv.push(mv);
+                            }
+                            _ => break,
+                        }
+                    }
+                    break;
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            }
+            i += 2;
+        }
+        (has_pv || info.score.is_some()).then_some(info)
+    }
+
+    /// Reads up to the engine's `bestmove`, telling it to stop as soon as
+    /// `stop` is signalled.
+    fn wait_for_best_move(
+        &mut self,
+        position: &Position,
+        deadline: Instant,
+        timeout: Duration,
+        stop: &StopSignal,
+    ) -> Result<ChessMove, PlayerError> {
+        let mut stop_sent = false;
+        loop {
+            if !stop_sent && stop.is_stopped() {
+                self.send("stop")?;
+                stop_sent = true;
+            }
+            let wait_until = deadline.min(Instant::now() + STOP_POLL);
+            let line = match self.read_line(wait_until, timeout) {
+                Err(PlayerError::Timeout(_)) if Instant::now() < deadline => continue,
+                line => line?,
+            };
+            if line.starts_with("info ") {
+                if let Some(info) = Self::parse_info(&line, position) {
+                    self.last_info = Some(info);
+                }
+                continue;
+            }
+            let Some(rest) = line.strip_prefix("bestmove") else {
+                continue;
+            };
+            let text = rest.split_whitespace().next().unwrap_or_default();
+            return match parse_move(text) {
+                Ok(mv) if position.is_legal(&mv) => Ok(mv),
+                _ if text == "0000" || text == "(none)" => Err(PlayerError::NoMove),
+                _ => Err(PlayerError::IllegalMove(text.to_string())),
+            };
+        }
+    }
+
+    /// Whether the engine process is still running.
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+}
+
+impl Player for UciClient {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), PlayerError> {
+        self.send("ucinewgame")?;
+        self.is_ready()
+    }
+
+    fn choose_move(
+        &mut self,
+        game: &Game,
+        request: &MoveRequest,
+    ) -> Result<ChessMove, PlayerError> {
+        if !self.is_alive() {
+            return Err(PlayerError::Crashed);
+        }
+        let position = *game.position();
+        self.last_info = None;
+        self.send(&Self::position_command(game))?;
+        self.send(&Self::go_command(request))?;
+
+        let timeout = self.move_deadline(request, position.side_to_move());
+        let stop = &request.limits.stop;
+        match self.wait_for_best_move(&position, Instant::now() + timeout, timeout, stop) {
+            Err(PlayerError::Timeout(_)) => {
+                // Ask for whatever it has, then give up on the process.
+                self.send("stop")?;
+                let grace = Instant::now() + MOVE_GRACE;
+                self.wait_for_best_move(&position, grace, timeout, &StopSignal::default())
+                    .inspect_err(|_| {
+                        let _ = self.child.kill();
+                        let _ = self.child.wait();
+                    })
+            }
+            result => result,
+        }
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_info.as_ref().and_then(|info| info.score)
+    }
+}
+
+impl Drop for UciClient {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_pv_stops_at_the_first_illegal_move() {
+        let line = "info depth 7 score cp -35 nodes 900 pv e2e4 e7e5 e4e5 g1f3";
+        let info = UciClient::parse_info(line, &Position::default()).unwrap();
+        assert_eq!(info.depth, 7);
+        assert_eq!(info.score, Some(-35));
+        assert_eq!(info.nodes, 900);
+        let pv: Vec<String> = info.pv.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(pv, ["e2e4", "e7e5"]);
+    }
+
+    #[test]
+    fn info_mate_scores() {
+        let start = Position::default();
+        let score = |line| UciClient::parse_info(line, &start).and_then(|info| info.score);
+        assert_eq!(score("info score mate 1"), Some(MATE_SCORE - 1));
+        assert_eq!(score("info score mate -3"), Some(-MATE_SCORE + 6));
+        assert_eq!(score("info string hello"), None);
+    }
+
+    #[test]
+    fn go_command_lists_every_limit() {
+        let request = MoveRequest {
+            limits: SearchLimits {
+                depth: Some(5),
+                nodes: Some(1000),
+                movetime: Some(Duration::from_millis(250)),
+                search_moves: vec![parse_move("e2e4").unwrap()],
+                ..SearchLimits::default()
+            },
+            clock: None,
+        };
+        assert_eq!(
+            UciClient::go_command(&request),
+            "go depth 5 nodes 1000 movetime 250 searchmoves e2e4"
+        );
+        assert_eq!(
+            UciClient::go_command(&MoveRequest::default()),
+            "go infinite"
+        );
+    }
+}
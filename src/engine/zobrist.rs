@@ -0,0 +1,77 @@
+use crate::engine::{
+    board::BoardGame,
+    chess_move::Square,
+    piece::{Piece, PieceColor},
+    position::CastlingRights,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::sync::OnceLock;
+
+// Fixed seed so hashes are identical across runs and threads.
+const ZOBRIST_SEED: u64 = 0x5EED_C4E5_5000_0001;
+
+struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 16],
+    en_passant_file: [u64; 8],
+}
+
+fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+        let mut pieces = [[0u64; 64]; 12];
+        for table in pieces.iter_mut() {
+            for key in table.iter_mut() {
+                *key = rng.random();
+            }
+        }
+        let black_to_move = rng.random();
+        let castling = std::array::from_fn(|_| rng.random());
+        let en_passant_file = std::array::from_fn(|_| rng.random());
+        ZobristKeys {
+            pieces,
+            black_to_move,
+            castling,
+            en_passant_file,
+        }
+    })
+}
+
+pub fn piece_key(piece: Piece, square: Square) -> u64 {
+    keys().pieces[piece.index()][square.index()]
+}
+
+pub fn side_key() -> u64 {
+    keys().black_to_move
+}
+
+pub fn castling_key(rights: CastlingRights) -> u64 {
+    keys().castling[rights.bits() as usize]
+}
+
+pub fn en_passant_key(square: Option<Square>) -> u64 {
+    square.map_or(0, |square| keys().en_passant_file[square.col()])
+}
+
+pub fn hash_position(
+    board: &BoardGame,
+    side_to_move: PieceColor,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+) -> u64 {
+    let mut hash = 0;
+    for (row, cells) in board.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            if let Some(piece) = cell {
+                let square = Square::try_from((row as u8, col as u8)).unwrap();
+                hash ^= piece_key(*piece, square);
+            }
+        }
+    }
+    if side_to_move == PieceColor::Black {
+        hash ^= side_key();
+    }
+    hash ^ castling_key(castling) ^ en_passant_key(en_passant)
+}
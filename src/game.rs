@@ -0,0 +1,278 @@
+use crate::engine::{
+    chess_move::{ChessMove, Square},
+    error::GameError,
+    piece::{PieceColor, PieceType},
+    position::Position,
+};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn win_for(color: PieceColor) -> Self {
+        match color {
+            PieceColor::White => GameResult::WhiteWins,
+            PieceColor::Black => GameResult::BlackWins,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    /// PGN result token.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+    /// A player ran out of time.
+    Timeout,
+    /// Stopped by the arbiter, e.g. a match runner's resign or draw rules.
+    Adjudication,
+    /// A player crashed, stalled or sent an illegal move.
+    Forfeit,
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::FiftyMoveRule => "fifty-move rule",
+            Termination::ThreefoldRepetition => "threefold repetition",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::Timeout => "time forfeit",
+            Termination::Adjudication => "adjudication",
+            Termination::Forfeit => "forfeit",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+/// Pieces each side has taken and the material balance they leave.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Material {
+    /// Black pieces taken by White, in the order they were captured.
+    pub captured_by_white: Vec<PieceType>,
+    pub captured_by_black: Vec<PieceType>,
+    /// White's material minus Black's on the board, in pawns, so that
+    /// promotions count too.
+    pub balance: i32,
+}
+
+impl Material {
+    pub fn captured_by(&self, color: PieceColor) -> &[PieceType] {
+        match color {
+            PieceColor::White => &self.captured_by_white,
+            PieceColor::Black => &self.captured_by_black,
+        }
+    }
+}
+
+/// Conventional value of a piece in pawns; the king has none.
+pub fn material_points(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight | PieceType::Bishop => 3,
+        PieceType::Rook => 5,
+        PieceType::Queen => 9,
+        PieceType::King => 0,
+    }
+}
+
+/// A game record: the starting position and every move played since, with
+/// the position reached after each of them.
+#[derive(Clone)]
+pub struct Game {
+    positions: Vec<Position>,
+    moves: Vec<ChessMove>,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_position(Position::default())
+    }
+
+    pub fn from_position(start: Position) -> Self {
+        Self {
+            positions: vec![start],
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn start_position(&self) -> &Position {
+        &self.positions[0]
+    }
+
+    pub fn position(&self) -> &Position {
+        self.positions.last().expect("a game always has a position")
+    }
+
+    /// Position after the first `ply` moves; `0` is the starting position.
+    pub fn position_at(&self, ply: usize) -> Option<&Position> {
+        self.positions.get(ply)
+    }
+
+    pub fn moves(&self) -> &[ChessMove] {
+        &self.moves
+    }
+
+    pub fn ply_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Plays a legal move. Draws by repetition, the fifty-move rule or lack
+    /// of material do not stop the game here: ending it is up to the caller.
+    pub fn play(&mut self, mv: ChessMove) -> Result<(), GameError> {
+        let position = self.position();
+        if !position.is_legal(&mv) {
+            return Err(GameError::IllegalMove(mv.to_string()));
+        }
+        let next = position.play(&mv);
+        self.positions.push(next);
+        self.moves.push(mv);
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<ChessMove> {
+        let mv = self.moves.pop()?;
+        self.positions.pop();
+        Some(mv)
+    }
+
+    /// Captures in the first `ply` moves and the material balance after
+    /// them.
+    pub fn material(&self, ply: usize) -> Material {
+        let mut material = Material::default();
+        for (position, mv) in self.positions.iter().zip(&self.moves).take(ply) {
+            let mover = position.side_to_move();
+            let captured = position
+                .captured_square(mv)
+                .and_then(|square| position.piece_at(square));
+            if let Some(captured) = captured {
+                match mover {
+                    PieceColor::White => material.captured_by_white.push(captured.piece_type),
+                    PieceColor::Black => material.captured_by_black.push(captured.piece_type),
+                }
+            }
+        }
+        let position = &self.positions[ply.min(self.moves.len())];
+        material.balance = (0..64u8)
+            .filter_map(|index| Square::try_from(index).ok())
+            .filter_map(|square| position.piece_at(square))
+            .map(|piece| match piece.color {
+                PieceColor::White => material_points(piece.piece_type),
+                PieceColor::Black => -material_points(piece.piece_type),
+            })
+            .sum();
+        material
+    }
+
+    /// Hashes of every position before the current one, oldest first, as
+    /// expected by `Searcher::search`.
+    pub fn history_hashes(&self) -> Vec<u64> {
+        self.positions[..self.positions.len() - 1]
+            .iter()
+            .map(|position| position.hash())
+            .collect()
+    }
+
+    fn repetition_count(&self) -> usize {
+        let current = self.position().hash();
+        self.positions
+            .iter()
+            .filter(|position| position.hash() == current)
+            .count()
+    }
+
+    /// How the game ended, if it did, by the rules alone (no clock or
+    /// resignation).
+    pub fn outcome(&self) -> Option<Outcome> {
+        let position = self.position();
+        let side = position.side_to_move();
+        let draw = |termination| {
+            Some(Outcome {
+                result: GameResult::Draw,
+                termination,
+            })
+        };
+
+        if position.legal_moves().is_empty() {
+            return if position.is_in_check(side) {
+                Some(Outcome {
+                    result: GameResult::win_for(side.opposite()),
+                    termination: Termination::Checkmate,
+                })
+            } else {
+                draw(Termination::Stalemate)
+            };
+        }
+        if position.is_insufficient_material() {
+            return draw(Termination::InsufficientMaterial);
+        }
+        if position.halfmove_clock() >= 100 {
+            return draw(Termination::FiftyMoveRule);
+        }
+        if self.repetition_count() >= 3 {
+            return draw(Termination::ThreefoldRepetition);
+        }
+        None
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    fn game_of(moves: &[&str]) -> Game {
+        let mut game = Game::new();
+        for mv in moves {
+            game.play(parse_move(mv).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn material_counts_captures_including_en_passant() {
+        // 1. e4 d5 2. e5 f5 3. exf6 gxf6
+        let game = game_of(&["e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "g7f6"]);
+        let after_en_passant = game.material(5);
+        assert_eq!(after_en_passant.captured_by_white, [PieceType::Pawn]);
+        assert!(after_en_passant.captured_by_black.is_empty());
+        assert_eq!(after_en_passant.balance, 1);
+
+        let material = game.material(game.ply_count());
+        assert_eq!(material.captured_by_black, [PieceType::Pawn]);
+        assert_eq!(material.balance, 0);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod clock;
 pub mod engine;
-// pub mod game; 
\ No newline at end of file
+pub mod game;
+pub mod markup;
+pub mod pgn;
+pub mod tournament;
+pub mod tree;
@@ -0,0 +1,341 @@
+use crate::engine::chess_move::{Square, parse_position};
+use std::fmt;
+
+/// Colours of board annotations, with the letters used for them in PGN
+/// comments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl MarkColor {
+    pub fn letter(self) -> char {
+        match self {
+            MarkColor::Green => 'G',
+            MarkColor::Red => 'R',
+            MarkColor::Yellow => 'Y',
+            MarkColor::Blue => 'B',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'G' => Some(MarkColor::Green),
+            'R' => Some(MarkColor::Red),
+            'Y' => Some(MarkColor::Yellow),
+            'B' => Some(MarkColor::Blue),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: MarkColor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Highlight {
+    pub square: Square,
+    pub color: MarkColor,
+}
+
+/// Arrows and highlighted squares drawn on one position, stored in PGN
+/// comments as `[%cal Ge2e4]` and `[%csl Rd5]` commands.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Markup {
+    pub arrows: Vec<Arrow>,
+    pub highlights: Vec<Highlight>,
+}
+
+impl Markup {
+    pub fn is_empty(&self) -> bool {
+        self.arrows.is_empty() && self.highlights.is_empty()
+    }
+
+    /// Adds the arrow, recolours it if it is there in another colour, or
+    /// removes it if it is there in the same colour.
+    pub fn toggle_arrow(&mut self, from: Square, to: Square, color: MarkColor) {
+        let existing = self
+            .arrows
+            .iter()
+            .position(|arrow| arrow.from == from && arrow.to == to);
+        match existing {
+            Some(index) if self.arrows[index].color == color => {
+                self.arrows.remove(index);
+            }
+            Some(index) => self.arrows[index].color = color,
+            None => self.arrows.push(Arrow { from, to, color }),
+        }
+    }
+
+    /// Same as `toggle_arrow` for a highlighted square.
+    pub fn toggle_highlight(&mut self, square: Square, color: MarkColor) {
+        let existing = self
+            .highlights
+            .iter()
+            .position(|highlight| highlight.square == square);
+        match existing {
+            Some(index) if self.highlights[index].color == color => {
+                self.highlights.remove(index);
+            }
+            Some(index) => self.highlights[index].color = color,
+            None => self.highlights.push(Highlight { square, color }),
+        }
+    }
+
+    /// Splits the `%cal` and `%csl` commands out of a PGN comment, returning
+    /// the markup and the rest of the comment. Malformed entries are dropped,
+    /// and a square or arrow given twice keeps its last colour.
+    pub fn from_comment(comment: &str) -> (Markup, String) {
+        let mut markup = Markup::default();
+        let mut rest = String::new();
+        let mut remaining = comment;
+        while let Some(start) = remaining.find("[%") {
+            let Some(length) = remaining[start..].find(']') else {
+                break;
+            };
+            let end = start + length + 1;
+            let command = &remaining[start + 2..end - 1];
+            let (name, arguments) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            let entries = arguments.split(',').map(str::trim);
+            match name {
+                "cal" => entries
+                    .filter_map(parse_arrow)
+                    .for_each(|arrow| markup.set_arrow(arrow)),
+                "csl" => entries
+                    .filter_map(parse_highlight)
+                    .for_each(|highlight| markup.set_highlight(highlight)),
+                // Other commands, such as `%clk`, stay in the comment.
+                _ => {
+                    rest.push_str(&remaining[..end]);
+                    remaining = &remaining[end..];
+                    continue;
+                }
+            }
+            // The text on either side of the command is joined by one space.
+            rest.push_str(&remaining[..start]);
+            rest.truncate(rest.trim_end().len());
+            remaining = remaining[end..].trim_start();
+            if !rest.is_empty() && !remaining.is_empty() {
+                rest.push(' ');
+            }
+        }
+        rest.push_str(remaining);
+        (markup, rest)
+    }
+
+    /// Adds `arrow`, replacing one between the same squares.
+    fn set_arrow(&mut self, arrow: Arrow) {
+        match self
+            .arrows
+            .iter_mut()
+            .find(|other| other.from == arrow.from && other.to == arrow.to)
+        {
+            Some(existing) => existing.color = arrow.color,
+            None => self.arrows.push(arrow),
+        }
+    }
+
+    /// Adds `highlight`, replacing one on the same square.
+    fn set_highlight(&mut self, highlight: Highlight) {
+        match self
+            .highlights
+            .iter_mut()
+            .find(|other| other.square == highlight.square)
+        {
+            Some(existing) => existing.color = highlight.color,
+            None => self.highlights.push(highlight),
+        }
+    }
+}
+
+fn parse_arrow(entry: &str) -> Option<Arrow> {
+    let color = MarkColor::from_letter(entry.chars().next()?)?;
+    let squares = entry.get(1..)?;
+    if squares.len() != 4 {
+        return None;
+    }
+    Some(Arrow {
+        from: parse_position(squares.get(..2)?).ok()?,
+        to: parse_position(squares.get(2..)?).ok()?,
+        color,
+    })
+}
+
+fn parse_highlight(entry: &str) -> Option<Highlight> {
+    let color = MarkColor::from_letter(entry.chars().next()?)?;
+    Some(Highlight {
+        square: parse_position(entry.get(1..)?).ok()?,
+        color,
+    })
+}
+
+impl fmt::Display for Markup {
+    /// The PGN commands, e.g. `[%csl Rd5][%cal Ge2e4,Gd2d4]`; nothing when
+    /// empty.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.highlights.is_empty() {
+            let entries: Vec<String> = self
+                .highlights
+                .iter()
+                .map(|highlight| format!("{}{}", highlight.color.letter(), highlight.square))
+                .collect();
+            write!(f, "[%csl {}]", entries.join(","))?;
+        }
+        if !self.arrows.is_empty() {
+            let entries: Vec<String> = self
+                .arrows
+                .iter()
+                .map(|arrow| format!("{}{}{}", arrow.color.letter(), arrow.from, arrow.to))
+                .collect();
+            write!(f, "[%cal {}]", entries.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Square {
+        parse_position(name).unwrap()
+    }
+
+    fn arrow(color: MarkColor, from: &str, to: &str) -> Arrow {
+        Arrow {
+            from: square(from),
+            to: square(to),
+            color,
+        }
+    }
+
+    #[test]
+    fn commands_are_split_from_the_text() {
+        let (markup, rest) =
+            Markup::from_comment("Good move [%csl Rd5,Gf7] threatening [%cal Ge2e4] mate");
+        assert_eq!(rest, "Good move threatening mate");
+        assert_eq!(markup.arrows, [arrow(MarkColor::Green, "e2", "e4")]);
+        assert_eq!(
+            markup.highlights,
+            [
+                Highlight {
+                    square: square("d5"),
+                    color: MarkColor::Red,
+                },
+                Highlight {
+                    square: square("f7"),
+                    color: MarkColor::Green,
+                },
+            ]
+        );
+
+        let (markup, rest) = Markup::from_comment("[%cal Bh1h8] [%csl Yh8]");
+        assert_eq!(rest, "");
+        assert_eq!(markup.arrows.len(), 1);
+        assert_eq!(markup.highlights.len(), 1);
+    }
+
+    #[test]
+    fn malformed_entries_are_dropped() {
+        let (markup, rest) =
+            Markup::from_comment("[%cal Ge2e4,Xe2e4,ge2e4,Ge2,Ge2e9,e2e4, Rd1d8 ,Ga1a2a3]");
+        assert_eq!(rest, "");
+        assert_eq!(
+            markup.arrows,
+            [
+                arrow(MarkColor::Green, "e2", "e4"),
+                arrow(MarkColor::Red, "d1", "d8"),
+            ]
+        );
+
+        let (markup, _) = Markup::from_comment("[%csl Qd5,Rz1,R,Yd5d6,Bc3][%cal]");
+        assert_eq!(
+            markup.highlights,
+            [Highlight {
+                square: square("c3"),
+                color: MarkColor::Blue,
+            }]
+        );
+        assert!(markup.arrows.is_empty());
+    }
+
+    #[test]
+    fn duplicates_keep_the_last_colour() {
+        let (markup, _) = Markup::from_comment("[%cal Ge2e4,Re2e4,Ge4e2][%csl Gd5][%csl Bd5]");
+        assert_eq!(
+            markup.arrows,
+            [
+                arrow(MarkColor::Red, "e2", "e4"),
+                arrow(MarkColor::Green, "e4", "e2"),
+            ]
+        );
+        assert_eq!(markup.highlights.len(), 1);
+        assert_eq!(markup.highlights[0].color, MarkColor::Blue);
+    }
+
+    #[test]
+    fn other_text_survives_untouched() {
+        for comment in [
+            "",
+            "Only words,  with  spacing\nand a newline",
+            "[%clk 0:01:30] time is short",
+            "[%eval +0.35]",
+            "[%cal Ge2e4 never closed",
+            "an [ordinary] bracket",
+        ] {
+            let (markup, rest) = Markup::from_comment(comment);
+            assert!(markup.is_empty(), "{comment}");
+            assert_eq!(rest, comment);
+        }
+
+        let (_, rest) = Markup::from_comment("Start [%csl Rd5] [%clk 0:01:30]  end");
+        assert_eq!(rest, "Start [%clk 0:01:30]  end");
+    }
+
+    #[test]
+    fn display_writes_highlights_then_arrows() {
+        let mut markup = Markup::default();
+        assert_eq!(markup.to_string(), "");
+        markup.toggle_arrow(square("e2"), square("e4"), MarkColor::Green);
+        markup.toggle_arrow(square("d2"), square("d4"), MarkColor::Yellow);
+        markup.toggle_highlight(square("d5"), MarkColor::Red);
+        assert_eq!(markup.to_string(), "[%csl Rd5][%cal Ge2e4,Yd2d4]");
+    }
+
+    #[test]
+    fn markup_reads_back_what_it_writes() {
+        let (markup, rest) =
+            Markup::from_comment("Plan [%cal Ge2e4, Rg1f3,Re2e4] [%csl Bd5,Yh7] here");
+        let written = markup.to_string();
+        let (reread, reread_rest) = Markup::from_comment(&written);
+        assert_eq!(reread, markup);
+        assert_eq!(reread_rest, "");
+        assert_eq!(reread.to_string(), written);
+
+        let comment = format!("{} {}", rest, written);
+        let (again, again_rest) = Markup::from_comment(&comment);
+        assert_eq!(again, markup);
+        assert_eq!(again_rest, rest);
+    }
+
+    #[test]
+    fn toggling_adds_recolours_and_removes() {
+        let mut markup = Markup::default();
+        markup.toggle_arrow(square("e2"), square("e4"), MarkColor::Green);
+        markup.toggle_arrow(square("e2"), square("e4"), MarkColor::Red);
+        assert_eq!(markup.arrows, [arrow(MarkColor::Red, "e2", "e4")]);
+        markup.toggle_arrow(square("e2"), square("e4"), MarkColor::Red);
+        markup.toggle_highlight(square("a1"), MarkColor::Blue);
+        markup.toggle_highlight(square("a1"), MarkColor::Blue);
+        assert!(markup.is_empty());
+    }
+}
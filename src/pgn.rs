@@ -0,0 +1,551 @@
+use crate::engine::{
+    error::PgnError,
+    piece::PieceColor,
+    position::{Position, STARTING_FEN},
+    san::{parse_san, to_san},
+};
+use crate::game::{Game, GameResult};
+use crate::tree::{GameTree, NodeId};
+use std::fmt;
+
+/// Tags every PGN game carries, in the order they are written.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Move assessment glyphs that may follow a move, by NAG number.
+const NAG_SYMBOLS: [(u8, &str); 6] = [
+    (1, "!"),
+    (2, "?"),
+    (3, "!!"),
+    (4, "??"),
+    (5, "!?"),
+    (6, "?!"),
+];
+
+/// `!` for NAG 1, and so on up to `?!` for 6.
+pub fn nag_symbol(nag: u8) -> Option<&'static str> {
+    NAG_SYMBOLS
+        .iter()
+        .find(|(number, _)| *number == nag)
+        .map(|(_, symbol)| *symbol)
+}
+
+fn nag_from_symbol(symbol: &str) -> Option<u8> {
+    NAG_SYMBOLS
+        .iter()
+        .find(|(_, known)| *known == symbol)
+        .map(|(number, _)| *number)
+}
+
+/// A game with its PGN tag pairs, variations, comments and annotation
+/// glyphs.
+#[derive(Clone)]
+pub struct PgnGame {
+    tags: Vec<(String, String)>,
+    pub tree: GameTree,
+}
+
+impl PgnGame {
+    /// Wraps `game` with the Seven Tag Roster set to unknown values.
+    pub fn new(game: Game) -> Self {
+        Self::from_tree(GameTree::from_game(&game))
+    }
+
+    /// Same as `new` for a game with variations.
+    pub fn from_tree(tree: GameTree) -> Self {
+        let tags = SEVEN_TAG_ROSTER
+            .iter()
+            .map(|&name| {
+                let value = match name {
+                    "Date" => "????.??.??",
+                    "Result" => "*",
+                    _ => "?",
+                };
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        Self { tags, tree }
+    }
+
+    /// The main line.
+    pub fn game(&self) -> Game {
+        let end = *self
+            .tree
+            .mainline()
+            .last()
+            .expect("the root is on the main line");
+        self.tree.game_at(end)
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.tags.push((name.to_string(), value)),
+        }
+    }
+
+    /// Result from the `Result` tag; `None` for a game in progress.
+    pub fn result(&self) -> Option<GameResult> {
+        match self.tag("Result")? {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+
+    pub fn set_result(&mut self, result: Option<GameResult>) {
+        let token = result.map_or_else(|| "*".to_string(), |result| result.to_string());
+        self.set_tag("Result", token);
+    }
+
+    /// Comment following the main-line move that leads to `ply`, or
+    /// preceding the moves for ply 0.
+    pub fn comment(&self, ply: usize) -> Option<&str> {
+        let node = *self.tree.mainline().get(ply)?;
+        Some(self.tree.comment(node)).filter(|comment| !comment.is_empty())
+    }
+
+    /// Sets the comment at `ply` of the main line; an empty text removes it.
+    pub fn set_comment(&mut self, ply: usize, text: impl Into<String>) {
+        if let Some(&node) = self.tree.mainline().get(ply) {
+            let text = text.into();
+            self.tree.set_comment(node, text.trim());
+        }
+    }
+
+    /// Move text in SAN with move numbers, variations, annotation glyphs
+    /// and comments, without line breaks.
+    pub fn movetext(&self) -> String {
+        let mut tokens = Vec::new();
+        self.push_comment(&mut tokens, GameTree::ROOT);
+        self.push_line(&mut tokens, GameTree::ROOT, true);
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+        tokens.join(" ")
+    }
+
+    /// Comment of `node` on one line, or `None` when there is no text.
+    fn comment_text(&self, node: NodeId) -> Option<String> {
+        let words: Vec<&str> = self.tree.comment(node).split_whitespace().collect();
+        (!words.is_empty()).then(|| words.join(" "))
+    }
+
+    fn push_comment(&self, tokens: &mut Vec<String>, node: NodeId) {
+        if let Some(comment) = self.comment_text(node) {
+            // A closing brace would end the comment early.
+            tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+        }
+    }
+
+    /// Writes the moves after `node`, each followed by its alternatives in
+    /// parentheses. `numbered` asks for the number before a move by Black.
+    fn push_line(&self, tokens: &mut Vec<String>, node: NodeId, mut numbered: bool) {
+        let mut current = node;
+        while let Some((&main, variations)) = self.tree.children(current).split_first() {
+            self.push_move(tokens, main, numbered);
+            for &variation in variations {
+                let first = tokens.len();
+                self.push_move(tokens, variation, true);
+                self.push_line(tokens, variation, self.comment_text(variation).is_some());
+                tokens[first].insert(0, '(');
+                tokens.last_mut().expect("a variation has a move").push(')');
+            }
+            // Black's move is numbered again after a comment or variation.
+            numbered = !variations.is_empty() || self.comment_text(main).is_some();
+            current = main;
+        }
+    }
+
+    fn push_move(&self, tokens: &mut Vec<String>, node: NodeId, numbered: bool) {
+        let parent = self.tree.parent(node).expect("a move has a parent");
+        let mv = self.tree.mv(node).expect("a move has a parent");
+        let position = self.tree.position(parent);
+        let number = position.fullmove_number();
+        match position.side_to_move() {
+            PieceColor::White => tokens.push(format!("{}.", number)),
+            PieceColor::Black if numbered => tokens.push(format!("{}...", number)),
+            PieceColor::Black => {}
+        }
+        tokens.push(to_san(position, &mv));
+        tokens.extend(self.tree.nags(node).iter().map(|nag| format!("${}", nag)));
+        self.push_comment(tokens, node);
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let roster = SEVEN_TAG_ROSTER
+            .iter()
+            .filter_map(|&name| self.tags.iter().find(|(tag, _)| tag == name));
+        let others = self
+            .tags
+            .iter()
+            .filter(|(tag, _)| !SEVEN_TAG_ROSTER.contains(&tag.as_str()))
+            .filter(|(tag, _)| tag != "FEN" && tag != "SetUp");
+        for (name, value) in roster.chain(others) {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        let start = self.tree.start_position().to_fen();
+        if start != STARTING_FEN {
+            writeln!(f, "[SetUp \"1\"]")?;
+            writeln!(f, "[FEN \"{}\"]", start)?;
+        }
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for token in self.movetext().split(' ') {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Game being read: its tags, and the tree itself once the first move
+/// fixes the starting position.
+struct PendingGame {
+    tags: Vec<(String, String)>,
+    tree: Option<GameTree>,
+    // Node the next move is played from.
+    current: NodeId,
+    // Node to go back to at the end of each open variation, innermost last.
+    variations: Vec<NodeId>,
+    // Comment read before the first move of the game or of a variation,
+    // waiting for the node it belongs to.
+    leading_comment: String,
+    at_variation_start: bool,
+    has_content: bool,
+}
+
+fn append_comment(comment: &mut String, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if !comment.is_empty() {
+        comment.push(' ');
+    }
+    comment.push_str(text);
+}
+
+impl PendingGame {
+    fn new() -> Self {
+        Self {
+            tags: Vec::new(),
+            tree: None,
+            current: GameTree::ROOT,
+            variations: Vec::new(),
+            leading_comment: String::new(),
+            at_variation_start: false,
+            has_content: false,
+        }
+    }
+
+    fn tree(&mut self) -> Result<&mut GameTree, PgnError> {
+        if self.tree.is_none() {
+            let start = match self.tags.iter().find(|(tag, _)| tag == "FEN") {
+                Some((_, fen)) => Position::from_fen(fen)?,
+                None => Position::default(),
+            };
+            let mut tree = GameTree::new(start);
+            tree.set_comment(GameTree::ROOT, std::mem::take(&mut self.leading_comment));
+            self.tree = Some(tree);
+        }
+        Ok(self.tree.as_mut().expect("just created"))
+    }
+
+    /// Attaches a comment to the last move read, joining it to any comment
+    /// already there.
+    fn add_comment(&mut self, text: &str) {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return;
+        }
+        match &mut self.tree {
+            Some(tree) if !self.at_variation_start => {
+                let mut comment = tree.comment(self.current).to_string();
+                append_comment(&mut comment, &text);
+                tree.set_comment(self.current, comment);
+            }
+            _ => append_comment(&mut self.leading_comment, &text),
+        }
+        self.has_content = true;
+    }
+
+    fn add_nag(&mut self, nag: u8) {
+        if let Some(tree) = &mut self.tree
+            && self.current != GameTree::ROOT
+        {
+            let mut nags = tree.nags(self.current).to_vec();
+            nags.push(nag);
+            tree.set_nags(self.current, nags);
+        }
+    }
+
+    fn play_san(&mut self, san: &str) -> Result<(), PgnError> {
+        self.has_content = true;
+        let current = self.current;
+        let leading = match std::mem::take(&mut self.at_variation_start) {
+            true => std::mem::take(&mut self.leading_comment),
+            false => String::new(),
+        };
+        let tree = self.tree()?;
+        let position = tree.position(current);
+        let mv = parse_san(position, san).map_err(|source| PgnError::InvalidMove {
+            ply: tree.ply(current) + 1,
+            source,
+        })?;
+        let node = tree
+            .play(current, mv)
+            .expect("parse_san only returns legal moves");
+        if !leading.is_empty() {
+            let mut comment = leading;
+            append_comment(&mut comment, tree.comment(node));
+            tree.set_comment(node, comment);
+        }
+        self.current = node;
+        Ok(())
+    }
+
+    /// A variation replaces the last move read.
+    fn start_variation(&mut self) -> Result<(), PgnError> {
+        let parent = self
+            .tree
+            .as_ref()
+            .and_then(|tree| tree.parent(self.current))
+            .ok_or(PgnError::Unexpected('('))?;
+        self.variations.push(self.current);
+        self.current = parent;
+        self.at_variation_start = true;
+        Ok(())
+    }
+
+    fn end_variation(&mut self) -> Result<(), PgnError> {
+        self.current = self.variations.pop().ok_or(PgnError::Unexpected(')'))?;
+        self.at_variation_start = false;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<PgnGame, PgnError> {
+        if !self.variations.is_empty() {
+            return Err(PgnError::Unterminated("variation"));
+        }
+        self.tree()?;
+        let mut pgn = PgnGame::from_tree(self.tree.take().expect("created above"));
+        for (name, value) in self.tags {
+            pgn.set_tag(&name, value);
+        }
+        Ok(pgn)
+    }
+}
+
+/// Reads every game in `text` with its variations, comments and numeric
+/// annotation glyphs.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut games = Vec::new();
+    let mut pending = PendingGame::new();
+    let mut i = 0;
+    let mut at_line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            at_line_start = c == '\n';
+            i += 1;
+            continue;
+        }
+        let line_start = at_line_start;
+        at_line_start = false;
+        match c {
+            '%' if line_start => i = skip_until(&chars, i, '\n'),
+            ';' => i = skip_until(&chars, i, '\n'),
+            '{' => {
+                let end = skip_until(&chars, i, '}');
+                if end > chars.len() {
+                    return Err(PgnError::Unterminated("comment"));
+                }
+                let text: String = chars[i + 1..end - 1].iter().collect();
+                pending.add_comment(&text);
+                i = end;
+            }
+            '(' => {
+                pending.start_variation()?;
+                i += 1;
+            }
+            ')' => {
+                pending.end_variation()?;
+                i += 1;
+            }
+            '[' => {
+                if pending.tree.is_some() {
+                    games.push(pending.finish()?);
+                    pending = PendingGame::new();
+                }
+                let end = skip_until(&chars, i, ']');
+                if end > chars.len() {
+                    return Err(PgnError::Unterminated("tag pair"));
+                }
+                let inner: String = chars[i + 1..end - 1].iter().collect();
+                pending.tags.push(parse_tag(&inner)?);
+                pending.has_content = true;
+                i = end;
+            }
+            '}' | ']' => return Err(PgnError::Unexpected(c)),
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"{}()[];".contains(chars[i])
+                {
+                    i += 1;
+                }
+                let token: String = chars[start..i].iter().collect();
+                if matches!(token.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+                    if !pending.tags.iter().any(|(tag, _)| tag == "Result") {
+                        pending.tags.push(("Result".to_string(), token));
+                    }
+                    games.push(pending.finish()?);
+                    pending = PendingGame::new();
+                    continue;
+                }
+                if let Some(number) = token.strip_prefix('$') {
+                    if let Ok(nag) = number.parse() {
+                        pending.add_nag(nag);
+                    }
+                    continue;
+                }
+                // Move numbers may be glued to the move, as in `12.Nf3`.
+                let san = token.rsplit('.').next().unwrap_or_default();
+                if san.is_empty() {
+                    continue;
+                }
+                let suffix_start = san.trim_end_matches(['!', '?']).len();
+                let (san, suffix) = san.split_at(suffix_start);
+                pending.play_san(san)?;
+                if let Some(nag) = nag_from_symbol(suffix) {
+                    pending.add_nag(nag);
+                }
+            }
+        }
+    }
+    if pending.has_content {
+        games.push(pending.finish()?);
+    }
+    Ok(games)
+}
+
+/// Index just past the first `end` at or after `start + 1`, or past the end
+/// of the input when there is none.
+fn skip_until(chars: &[char], start: usize, end: char) -> usize {
+    chars[start + 1..]
+        .iter()
+        .position(|&c| c == end)
+        .map_or(chars.len() + 1, |offset| start + offset + 2)
+}
+
+fn parse_tag(inner: &str) -> Result<(String, String), PgnError> {
+    let invalid = || PgnError::InvalidTag(inner.to_string());
+    let (name, rest) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let quoted = rest.trim();
+    let value = quoted
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok((name.to_string(), unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    fn play(tree: &mut GameTree, node: NodeId, mv: &str) -> NodeId {
+        tree.play(node, parse_move(mv).unwrap()).unwrap()
+    }
+
+    /// Every move of the tree below `node` with its glyphs, comment and
+    /// variations, in order.
+    fn outline(tree: &GameTree, node: NodeId) -> String {
+        let children: Vec<String> = tree
+            .children(node)
+            .iter()
+            .map(|&child| outline(tree, child))
+            .collect();
+        format!(
+            "{}{:?}{{{}}}({})",
+            tree.mv(node)
+                .map_or("root".to_string(), |mv| mv.to_string()),
+            tree.nags(node),
+            tree.comment(node),
+            children.join(" ")
+        )
+    }
+
+    #[test]
+    fn tree_round_trips_through_pgn() {
+        let mut tree = GameTree::new(Position::default());
+        tree.set_comment(GameTree::ROOT, "A short game");
+        let e4 = play(&mut tree, GameTree::ROOT, "e2e4");
+        tree.set_nags(e4, vec![1]);
+        tree.set_comment(e4, "Best by test");
+        let d4 = play(&mut tree, GameTree::ROOT, "d2d4");
+        tree.set_nags(d4, vec![5, 14]);
+        let e5 = play(&mut tree, e4, "e7e5");
+        let c5 = play(&mut tree, e4, "c7c5");
+        tree.set_comment(c5, "The Sicilian");
+        let nf3 = play(&mut tree, c5, "g1f3");
+        play(&mut tree, nf3, "d7d6");
+        let nc6 = play(&mut tree, nf3, "b8c6");
+        tree.set_nags(nc6, vec![6]);
+        let nf3 = play(&mut tree, e5, "g1f3");
+        tree.set_comment(nf3, "Heading for the \"Italian\"");
+
+        let mut pgn = PgnGame::from_tree(tree);
+        pgn.set_tag("Event", "Club \\ championship");
+        let text = pgn.to_string();
+        let games = parse_pgn(&text).unwrap();
+        assert_eq!(games.len(), 1, "{}", text);
+        let read = &games[0];
+
+        assert_eq!(
+            outline(&read.tree, GameTree::ROOT),
+            outline(&pgn.tree, GameTree::ROOT),
+            "{}",
+            text
+        );
+        assert_eq!(read.tags(), pgn.tags());
+        assert_eq!(read.to_string(), text);
+    }
+}
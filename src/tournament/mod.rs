@@ -0,0 +1,646 @@
+pub mod openings;
+pub mod stats;
+
+use crate::clock::{self, ChessClock, Timing, timeout_outcome};
+use crate::engine::{
+    error::TournamentError,
+    piece::PieceColor,
+    player::{MoveRequest, Player},
+    search::SearchLimits,
+};
+use crate::game::{Game, GameResult, Outcome, Termination};
+use crate::pgn::PgnGame;
+use openings::Opening;
+use stats::{Score, Sprt, SprtState};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How much thinking each move gets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// Each player has a game clock.
+    Clock(clock::TimeControl),
+    MoveTime(Duration),
+    Depth(u8),
+    Nodes(u64),
+}
+
+impl TimeControl {
+    /// Value of the PGN `TimeControl` tag.
+    pub fn pgn_tag(&self) -> String {
+        match self {
+            TimeControl::Clock(control) => {
+                // Every stage carries the increment. The tag has no
+                // notation for delays.
+                let increment = match control.timing {
+                    Timing::Increment(increment) if !increment.is_zero() => {
+                        format!("+{}", format_seconds(increment))
+                    }
+                    _ => String::new(),
+                };
+                let stages: Vec<String> = control
+                    .stages
+                    .iter()
+                    .map(|stage| match stage.moves {
+                        Some(moves) => {
+                            format!("{}/{}{}", moves, format_seconds(stage.time), increment)
+                        }
+                        None => format!("{}{}", format_seconds(stage.time), increment),
+                    })
+                    .collect();
+                stages.join(":")
+            }
+            _ => "-".to_string(),
+        }
+    }
+}
+
+fn format_seconds(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds.fract() == 0.0 {
+        format!("{}", seconds as u64)
+    } else {
+        format!("{}", seconds)
+    }
+}
+
+/// Rules for ending games early on the players' own evaluations. A rule is
+/// off while its score is `None`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Adjudication {
+    /// Resign for a side both players see losing by at least this many
+    /// centipawns for `resign_moves` consecutive moves each.
+    pub resign_score: Option<i32>,
+    pub resign_moves: u32,
+    /// Draw once both players see the game within this many centipawns of
+    /// equal for `draw_moves` consecutive moves each, after `draw_min_ply`.
+    pub draw_score: Option<i32>,
+    pub draw_moves: u32,
+    pub draw_min_ply: usize,
+    /// Draw games that reach this many plies.
+    pub max_plies: Option<usize>,
+}
+
+/// Consecutive plies on which the adjudication conditions held.
+#[derive(Default)]
+struct Adjudicator {
+    white_losing: u32,
+    black_losing: u32,
+    drawn: u32,
+}
+
+impl Adjudicator {
+    /// Records the score reported with a move by `side`, returning the
+    /// adjudicated outcome once a rule applies.
+    fn record(
+        &mut self,
+        rules: &Adjudication,
+        side: PieceColor,
+        score: Option<i32>,
+        ply: usize,
+    ) -> Option<(Outcome, String)> {
+        let Some(score) = score else {
+            *self = Self::default();
+            return None;
+        };
+        let white_score = match side {
+            PieceColor::White => score,
+            PieceColor::Black => -score,
+        };
+        let counter = |count: &mut u32, holds: bool| *count = if holds { *count + 1 } else { 0 };
+
+        if let Some(threshold) = rules.resign_score {
+            counter(&mut self.white_losing, white_score <= -threshold);
+            counter(&mut self.black_losing, white_score >= threshold);
+            let needed = 2 * rules.resign_moves.max(1);
+            for (count, loser) in [
+                (self.white_losing, PieceColor::White),
+                (self.black_losing, PieceColor::Black),
+            ] {
+                if count >= needed {
+                    let outcome = Outcome {
+                        result: GameResult::win_for(loser.opposite()),
+                        termination: Termination::Adjudication,
+                    };
+                    return Some((outcome, format!("{} resigns", color_name(loser))));
+                }
+            }
+        }
+        if let Some(threshold) = rules.draw_score {
+            counter(&mut self.drawn, white_score.abs() <= threshold);
+            if ply >= rules.draw_min_ply && self.drawn >= 2 * rules.draw_moves.max(1) {
+                let outcome = Outcome {
+                    result: GameResult::Draw,
+                    termination: Termination::Adjudication,
+                };
+                return Some((outcome, "Draw by adjudication".to_string()));
+            }
+        }
+        None
+    }
+}
+
+fn color_name(color: PieceColor) -> &'static str {
+    match color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Every player meets every other one.
+    RoundRobin,
+    /// The first player meets each of the others.
+    Gauntlet,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchSettings {
+    pub format: Format,
+    /// Each round plays every pairing twice from the same opening, once with
+    /// each colour.
+    pub rounds: usize,
+    pub time_control: TimeControl,
+    /// Time a player may overstep its clock before losing on time.
+    pub time_margin: Duration,
+    pub adjudication: Adjudication,
+    /// Stops early once the first player's result against the field is
+    /// conclusive. Meant for gauntlets and two-player matches.
+    pub sprt: Option<Sprt>,
+    pub event: String,
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            format: Format::RoundRobin,
+            rounds: 1,
+            time_control: TimeControl::MoveTime(Duration::from_millis(100)),
+            time_margin: Duration::from_millis(50),
+            adjudication: Adjudication::default(),
+            sprt: None,
+            event: "Engine match".to_string(),
+        }
+    }
+}
+
+/// A finished game and why it ended.
+pub struct GameRecord {
+    pub game: Game,
+    pub outcome: Outcome,
+    pub reason: String,
+}
+
+/// Plays one game from `opening` and returns it once decided by the rules,
+/// the clock, adjudication or a player failing.
+pub fn play_game(
+    white: &mut dyn Player,
+    black: &mut dyn Player,
+    opening: &Opening,
+    settings: &MatchSettings,
+) -> GameRecord {
+    let mut game = opening.game();
+    let start_ply = game.ply_count();
+    let forfeit = |game: Game, loser: PieceColor, reason: String| GameRecord {
+        game,
+        outcome: Outcome {
+            result: GameResult::win_for(loser.opposite()),
+            termination: Termination::Forfeit,
+        },
+        reason,
+    };
+    if let Err(e) = white.new_game() {
+        return forfeit(game, PieceColor::White, format!("White forfeits: {}", e));
+    }
+    if let Err(e) = black.new_game() {
+        return forfeit(game, PieceColor::Black, format!("Black forfeits: {}", e));
+    }
+
+    let mut clock = match &settings.time_control {
+        TimeControl::Clock(control) => {
+            let mut clock = ChessClock::new(control.clone());
+            clock.set_grace(settings.time_margin);
+            // Unlike over the board, the first move is timed too.
+            clock.switch_to(game.position().side_to_move(), Instant::now());
+            Some(clock)
+        }
+        _ => None,
+    };
+    let mut adjudicator = Adjudicator::default();
+
+    loop {
+        if let Some(outcome) = game.outcome() {
+            let reason = match (outcome.termination, outcome.result) {
+                (Termination::Checkmate, GameResult::WhiteWins) => "White mates".to_string(),
+                (Termination::Checkmate, _) => "Black mates".to_string(),
+                (termination, _) => format!("Draw by {}", termination),
+            };
+            return GameRecord {
+                game,
+                outcome,
+                reason,
+            };
+        }
+        if settings
+            .adjudication
+            .max_plies
+            .is_some_and(|max| game.ply_count() - start_ply >= max)
+        {
+            let outcome = Outcome {
+                result: GameResult::Draw,
+                termination: Termination::Adjudication,
+            };
+            return GameRecord {
+                game,
+                outcome,
+                reason: "Draw by move limit".to_string(),
+            };
+        }
+
+        let side = game.position().side_to_move();
+        let player: &mut dyn Player = match side {
+            PieceColor::White => &mut *white,
+            PieceColor::Black => &mut *black,
+        };
+        let request = match settings.time_control {
+            TimeControl::Clock(_) => MoveRequest {
+                limits: SearchLimits::default(),
+                clock: clock.as_ref().map(|clock| clock.times(Instant::now())),
+            },
+            TimeControl::MoveTime(movetime) => MoveRequest {
+                limits: SearchLimits {
+                    movetime: Some(movetime),
+                    ..SearchLimits::default()
+                },
+                clock: None,
+            },
+            TimeControl::Depth(depth) => MoveRequest {
+                limits: SearchLimits {
+                    depth: Some(depth),
+                    ..SearchLimits::default()
+                },
+                clock: None,
+            },
+            TimeControl::Nodes(nodes) => MoveRequest {
+                limits: SearchLimits {
+                    nodes: Some(nodes),
+                    ..SearchLimits::default()
+                },
+                clock: None,
+            },
+        };
+
+        let chosen = player.choose_move(&game, &request);
+        let finished = Instant::now();
+        let mv = match chosen {
+            Ok(mv) => mv,
+            Err(e) => return forfeit(game, side, format!("{} forfeits: {}", color_name(side), e)),
+        };
+        if let Err(e) = game.play(mv) {
+            return forfeit(game, side, format!("{} forfeits: {}", color_name(side), e));
+        }
+
+        if let Some(clock) = &mut clock
+            && clock.press(side, finished).is_some()
+        {
+            let outcome = timeout_outcome(game.position(), side);
+            let reason = match outcome.result {
+                GameResult::Draw => {
+                    format!("{} flags, no mating material left", color_name(side))
+                }
+                _ => format!("{} loses on time", color_name(side)),
+            };
+            return GameRecord {
+                game,
+                outcome,
+                reason,
+            };
+        }
+
+        let ply = game.ply_count() - start_ply;
+        if let Some((outcome, reason)) =
+            adjudicator.record(&settings.adjudication, side, player.last_score(), ply)
+        {
+            return GameRecord {
+                game,
+                outcome,
+                reason,
+            };
+        }
+    }
+}
+
+/// One game of the schedule: indices into the player list and the opening
+/// suite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pairing {
+    pub round: usize,
+    pub white: usize,
+    pub black: usize,
+    pub opening: usize,
+}
+
+/// Results of every player against every other one.
+#[derive(Clone, Debug)]
+pub struct Standings {
+    names: Vec<String>,
+    // `results[a][b]` is player `a`'s score against player `b`.
+    results: Vec<Vec<Score>>,
+}
+
+impl Standings {
+    fn new(names: Vec<String>) -> Self {
+        let count = names.len();
+        Self {
+            names,
+            results: vec![vec![Score::default(); count]; count],
+        }
+    }
+
+    fn record(&mut self, white: usize, black: usize, result: GameResult) {
+        let score = &mut self.results[white][black];
+        match result {
+            GameResult::WhiteWins => score.wins += 1,
+            GameResult::BlackWins => score.losses += 1,
+            GameResult::Draw => score.draws += 1,
+        }
+        self.results[black][white] = self.results[white][black].reversed();
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, player: usize) -> &str {
+        &self.names[player]
+    }
+
+    /// Player `a`'s score against player `b`.
+    pub fn head_to_head(&self, a: usize, b: usize) -> Score {
+        self.results[a][b]
+    }
+
+    /// A player's score against the whole field.
+    pub fn score(&self, player: usize) -> Score {
+        self.results[player]
+            .iter()
+            .fold(Score::default(), |total, score| Score {
+                wins: total.wins + score.wins,
+                draws: total.draws + score.draws,
+                losses: total.losses + score.losses,
+            })
+    }
+
+    /// Players sorted by points, best first.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut players: Vec<usize> = (0..self.names.len()).collect();
+        players.sort_by(|&a, &b| self.score(b).points().total_cmp(&self.score(a).points()));
+        players
+    }
+}
+
+/// A finished game as reported to the caller while the tournament runs.
+pub struct GameReport {
+    pub number: usize,
+    pub pairing: Pairing,
+    pub record: GameRecord,
+    pub pgn: PgnGame,
+}
+
+pub struct Tournament {
+    players: Vec<Box<dyn Player>>,
+    openings: Vec<Opening>,
+    settings: MatchSettings,
+}
+
+impl Tournament {
+    pub fn new(
+        players: Vec<Box<dyn Player>>,
+        openings: Vec<Opening>,
+        settings: MatchSettings,
+    ) -> Result<Self, TournamentError> {
+        if players.len() < 2 {
+            return Err(TournamentError::NotEnoughPlayers);
+        }
+        if openings.is_empty() {
+            return Err(TournamentError::NoOpenings);
+        }
+        Ok(Self {
+            players,
+            openings,
+            settings,
+        })
+    }
+
+    pub fn settings(&self) -> &MatchSettings {
+        &self.settings
+    }
+
+    pub fn schedule(&self) -> Vec<Pairing> {
+        let count = self.players.len();
+        let pairs: Vec<(usize, usize)> = match self.settings.format {
+            Format::RoundRobin => (0..count)
+                .flat_map(|a| (a + 1..count).map(move |b| (a, b)))
+                .collect(),
+            Format::Gauntlet => (1..count).map(|b| (0, b)).collect(),
+        };
+        let mut schedule = Vec::new();
+        for round in 0..self.settings.rounds {
+            let opening = round % self.openings.len();
+            for &(a, b) in &pairs {
+                for (white, black) in [(a, b), (b, a)] {
+                    schedule.push(Pairing {
+                        round,
+                        white,
+                        black,
+                        opening,
+                    });
+                }
+            }
+        }
+        schedule
+    }
+
+    /// Plays the schedule, calling `on_game` after every game. Returns the
+    /// final standings and the SPRT verdict if the test ended the run.
+    pub fn run(
+        &mut self,
+        on_game: &mut dyn FnMut(&GameReport, &Standings),
+    ) -> (Standings, Option<SprtState>) {
+        let names: Vec<String> = self.players.iter().map(|player| player.name()).collect();
+        let mut standings = Standings::new(names);
+        let date = today();
+
+        for (index, pairing) in self.schedule().into_iter().enumerate() {
+            let opening = &self.openings[pairing.opening];
+            let (white, black) = pair_mut(&mut self.players, pairing.white, pairing.black);
+            let record = play_game(white.as_mut(), black.as_mut(), opening, &self.settings);
+            standings.record(pairing.white, pairing.black, record.outcome.result);
+
+            let mut pgn = PgnGame::new(record.game.clone());
+            pgn.set_tag("Event", self.settings.event.clone());
+            pgn.set_tag("Date", date.clone());
+            pgn.set_tag("Round", (pairing.round + 1).to_string());
+            pgn.set_tag("White", standings.name(pairing.white));
+            pgn.set_tag("Black", standings.name(pairing.black));
+            pgn.set_result(Some(record.outcome.result));
+            pgn.set_tag("TimeControl", self.settings.time_control.pgn_tag());
+            pgn.set_tag("Termination", pgn_termination(record.outcome.termination));
+            if let Some(name) = &opening.name {
+                pgn.set_tag("Opening", name.clone());
+            }
+
+            let report = GameReport {
+                number: index + 1,
+                pairing,
+                record,
+                pgn,
+            };
+            on_game(&report, &standings);
+
+            if let Some(sprt) = self.settings.sprt {
+                let state = sprt.state(&standings.score(0));
+                if state != SprtState::Continue {
+                    return (standings, Some(state));
+                }
+            }
+        }
+        (standings, None)
+    }
+}
+
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    assert_ne!(a, b, "a player cannot meet itself");
+    if a < b {
+        let (left, right) = items.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+fn pgn_termination(termination: Termination) -> &'static str {
+    match termination {
+        Termination::Timeout => "time forfeit",
+        Termination::Adjudication => "adjudication",
+        Termination::Forfeit => "rules infraction",
+        _ => "normal",
+    }
+}
+
+/// Current UTC date as `YYYY.MM.DD`.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / 86_400) as i64;
+    // Civil-from-days conversion for the proleptic Gregorian calendar.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Stage;
+    use crate::engine::{chess_move::ChessMove, error::PlayerError};
+    use std::thread;
+
+    /// Plays the first legal move after thinking for `delay`.
+    struct Slow {
+        delay: Duration,
+    }
+
+    impl Player for Slow {
+        fn name(&self) -> String {
+            "Slow".to_string()
+        }
+
+        fn choose_move(
+            &mut self,
+            game: &Game,
+            request: &MoveRequest,
+        ) -> Result<ChessMove, PlayerError> {
+            assert!(request.clock.is_some());
+            thread::sleep(self.delay);
+            Ok(game.position().legal_moves()[0])
+        }
+    }
+
+    #[test]
+    fn slow_player_loses_on_time() {
+        let settings = MatchSettings {
+            time_control: TimeControl::Clock(clock::TimeControl::fischer(
+                Duration::from_millis(200),
+                Duration::ZERO,
+            )),
+            time_margin: Duration::from_millis(20),
+            ..MatchSettings::default()
+        };
+        let mut white = Slow {
+            delay: Duration::ZERO,
+        };
+        let mut black = Slow {
+            delay: Duration::from_millis(80),
+        };
+        let record = play_game(&mut white, &mut black, &Opening::default(), &settings);
+        assert_eq!(record.outcome.termination, Termination::Timeout);
+        assert_eq!(record.outcome.result, GameResult::WhiteWins);
+        assert_eq!(record.reason, "Black loses on time");
+        // The third move takes Black to 240ms, over its 200ms and margin.
+        assert_eq!(record.game.ply_count(), 6);
+    }
+
+    #[test]
+    fn clock_is_written_as_a_pgn_tag() {
+        let tag = |control| TimeControl::Clock(control).pgn_tag();
+        assert_eq!(
+            tag(clock::TimeControl::fischer(
+                Duration::from_secs(300),
+                Duration::from_millis(100)
+            )),
+            "300+0.1"
+        );
+        let classical = clock::TimeControl {
+            stages: vec![
+                Stage {
+                    moves: Some(40),
+                    time: Duration::from_secs(5400),
+                },
+                Stage {
+                    moves: None,
+                    time: Duration::from_secs(1800),
+                },
+            ],
+            timing: Timing::Increment(Duration::from_secs(30)),
+        };
+        assert_eq!(tag(classical.clone()), "40/5400+30:1800+30");
+        let delayed = clock::TimeControl {
+            timing: Timing::Delay(Duration::from_secs(5)),
+            ..classical.clone()
+        };
+        assert_eq!(tag(delayed), "40/5400:1800");
+        let repeating = clock::TimeControl {
+            stages: vec![Stage {
+                moves: Some(40),
+                time: Duration::from_secs(7200),
+            }],
+            ..classical
+        };
+        assert_eq!(tag(repeating), "40/7200+30");
+        assert_eq!(TimeControl::Depth(5).pgn_tag(), "-");
+    }
+}
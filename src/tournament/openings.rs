@@ -0,0 +1,195 @@
+use crate::engine::{chess_move::ChessMove, error::TournamentError, position::Position};
+use crate::game::Game;
+use crate::pgn::parse_pgn;
+use std::fs;
+use std::path::Path;
+
+/// A starting point for match games: a position and the moves already
+/// played from it.
+#[derive(Clone)]
+pub struct Opening {
+    pub name: Option<String>,
+    pub start: Position,
+    pub moves: Vec<ChessMove>,
+}
+
+impl Opening {
+    pub fn game(&self) -> Game {
+        let mut game = Game::from_position(self.start);
+        for mv in &self.moves {
+            game.play(*mv)
+                .expect("opening moves were checked when loaded");
+        }
+        game
+    }
+}
+
+impl Default for Opening {
+    /// The standard starting position.
+    fn default() -> Self {
+        Self {
+            name: None,
+            start: Position::default(),
+            moves: Vec::new(),
+        }
+    }
+}
+
+/// Reads an EPD file, or a PGN file when the extension is `.pgn`.
+/// `max_plies` truncates PGN games to their first moves.
+pub fn load_openings(path: &Path, max_plies: usize) -> Result<Vec<Opening>, TournamentError> {
+    let text = fs::read_to_string(path).map_err(|source| TournamentError::Io {
+        path: path.display().to_string(),
+        source,
+    })?;
+    let is_pgn = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+    let openings = if is_pgn {
+        openings_from_pgn(&text, max_plies)?
+    } else {
+        parse_epd(&text)?
+    };
+    if openings.is_empty() {
+        return Err(TournamentError::NoOpenings);
+    }
+    Ok(openings)
+}
+
+/// One position per line: the four FEN fields, then optional operations of
+/// which only `id` is kept.
+pub fn parse_epd(text: &str) -> Result<Vec<Opening>, TournamentError> {
+    let mut openings = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| TournamentError::InvalidOpening {
+            line: index + 1,
+            message,
+        };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let start = Position::from_fen(&fields[..fields.len().min(4)].join(" "))
+            .map_err(|e| invalid(e.to_string()))?;
+        start
+            .validate()
+            .map_err(|e| invalid(format!("illegal position: {}", e)))?;
+        let operations = fields.get(4..).unwrap_or_default().join(" ");
+        let name = operations
+            .split(';')
+            .filter_map(|operation| operation.trim().strip_prefix("id "))
+            .map(|id| id.trim().trim_matches('"').to_string())
+            .next();
+        openings.push(Opening {
+            name,
+            start,
+            moves: Vec::new(),
+        });
+    }
+    Ok(openings)
+}
+
+pub fn openings_from_pgn(text: &str, max_plies: usize) -> Result<Vec<Opening>, TournamentError> {
+    let mut openings = Vec::new();
+    for (index, pgn) in parse_pgn(text)?.into_iter().enumerate() {
+        let name = pgn
+            .tag("Opening")
+            .or(pgn.tag("ECO"))
+            .or(pgn.tag("Event").filter(|&event| event != "?"))
+            .map(str::to_string);
+        let game = pgn.game();
+        let start = *game.start_position();
+        start
+            .validate()
+            .map_err(|source| TournamentError::IllegalOpeningGame {
+                game: index + 1,
+                source,
+            })?;
+        openings.push(Opening {
+            name,
+            start,
+            moves: game.moves().iter().take(max_plies).copied().collect(),
+        });
+    }
+    Ok(openings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::error::PositionError;
+
+    #[test]
+    fn epd_lines_with_operations() {
+        let text = "\
+# comment
+rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 id \"King's pawn\";
+
+r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5;
+";
+        let openings = parse_epd(text).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].name.as_deref(), Some("King's pawn"));
+        assert_eq!(
+            openings[0].start.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+        );
+        assert_eq!(openings[1].name, None);
+        assert!(openings[1].moves.is_empty());
+    }
+
+    #[test]
+    fn epd_fields_may_be_separated_by_several_spaces() {
+        let text = "4k3/8/8/8/8/8/8/4K3   w  -\t- id \"kings\";";
+        let openings = parse_epd(text).unwrap();
+        assert_eq!(openings[0].name.as_deref(), Some("kings"));
+    }
+
+    #[test]
+    fn epd_errors_name_the_line() {
+        let invalid = parse_epd("4k3/8/8/8/8/8/8/4K3 w - -\n8/8/8 w - -");
+        assert!(matches!(
+            invalid,
+            Err(TournamentError::InvalidOpening { line: 2, .. })
+        ));
+        let illegal = parse_epd("4k3/8/8/8/8/8/8/4KK2 w - -");
+        assert!(matches!(
+            illegal,
+            Err(TournamentError::InvalidOpening { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn pgn_openings_are_truncated() {
+        let text = "\
+[Event \"?\"]
+[Opening \"Ruy Lopez\"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 *
+
+[Event \"Gambits\"]
+
+1. e4 e5 2. f4 *
+";
+        let openings = openings_from_pgn(text, 4).unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].name.as_deref(), Some("Ruy Lopez"));
+        assert_eq!(openings[0].moves.len(), 4);
+        assert_eq!(openings[0].game().ply_count(), 4);
+        assert_eq!(openings[1].name.as_deref(), Some("Gambits"));
+        assert_eq!(openings[1].moves.len(), 3);
+    }
+
+    #[test]
+    fn pgn_opening_from_an_illegal_position_is_rejected() {
+        let text = "[FEN \"4k3/8/8/8/8/8/8/4KK2 w - - 0 1\"]\n\n*\n";
+        assert!(matches!(
+            openings_from_pgn(text, 8),
+            Err(TournamentError::IllegalOpeningGame {
+                game: 1,
+                source: PositionError::KingCount(_),
+            })
+        ));
+    }
+}
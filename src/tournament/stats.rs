@@ -0,0 +1,325 @@
+use std::fmt;
+
+/// Two-sided 95% quantile of the normal distribution.
+const Z_95: f64 = 1.959_964;
+
+/// Wins, draws and losses from one player's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// Points per game, between 0 and 1.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.games() > 0).then(|| self.points() / self.games() as f64)
+    }
+
+    /// The same games seen from the opponent's side.
+    pub fn reversed(&self) -> Score {
+        Score {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    /// Variance of a single game's result around the mean score.
+    fn variance(&self) -> Option<f64> {
+        let ratio = self.ratio()?;
+        let n = self.games() as f64;
+        let deviation = |result: f64| (result - ratio).powi(2);
+        Some(
+            (self.wins as f64 * deviation(1.0)
+                + self.draws as f64 * deviation(0.5)
+                + self.losses as f64 * deviation(0.0))
+                / n,
+        )
+    }
+
+    /// Elo difference with a 95% confidence interval. `None` until the score
+    /// is strictly between 0% and 100%, where the difference is unbounded.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let ratio = self.ratio()?;
+        if ratio <= 0.0 || ratio >= 1.0 {
+            return None;
+        }
+        let std_error = (self.variance()? / self.games() as f64).sqrt();
+        let low = elo_from_score((ratio - Z_95 * std_error).max(f64::EPSILON));
+        let high = elo_from_score((ratio + Z_95 * std_error).min(1.0 - f64::EPSILON));
+        Some(EloEstimate {
+            difference: elo_from_score(ratio),
+            margin: (high - low) / 2.0,
+        })
+    }
+
+    /// Likelihood of superiority: the probability that the player is really
+    /// the stronger one, from wins and losses alone.
+    pub fn likelihood_of_superiority(&self) -> Option<f64> {
+        let decisive = (self.wins + self.losses) as f64;
+        (decisive > 0.0).then(|| {
+            let x = (self.wins as f64 - self.losses as f64) / (2.0 * decisive).sqrt();
+            0.5 * (1.0 + erf(x))
+        })
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub difference: f64,
+    /// Half-width of the 95% confidence interval.
+    pub margin: f64,
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.1} +/- {:.1}", self.difference, self.margin)
+    }
+}
+
+/// Expected score of the stronger side for an Elo difference.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Abramowitz-Stegun approximation, accurate to about 1e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let value = 1.0 - poly * (-x * x).exp();
+    if x >= 0.0 { value } else { -value }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtState {
+    Continue,
+    /// The Elo difference is at most `elo0`: the change failed.
+    AcceptH0,
+    /// The Elo difference is at least `elo1`: the change passed.
+    AcceptH1,
+}
+
+/// Sequential probability ratio test between H0: `elo = elo0` and H1:
+/// `elo = elo1`, with false positive rate `alpha` and false negative rate
+/// `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        }
+    }
+
+    /// Log-likelihood ratio thresholds `(lower, upper)`.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of H1 over H0, using the normal approximation
+    /// of the trinomial model.
+    pub fn llr(&self, score: &Score) -> f64 {
+        let games = score.games() as f64;
+        if games == 0.0 {
+            return 0.0;
+        }
+        // Half a game of each result keeps the variance away from zero
+        // while one of the outcomes has not happened yet.
+        let prior = if score.wins == 0 || score.draws == 0 || score.losses == 0 {
+            0.5
+        } else {
+            0.0
+        };
+        let wins = score.wins as f64 + prior;
+        let draws = score.draws as f64 + prior;
+        let losses = score.losses as f64 + prior;
+        let total = wins + draws + losses;
+        let ratio = (wins + draws / 2.0) / total;
+        let variance =
+            (wins * (1.0 - ratio).powi(2) + draws * (0.5 - ratio).powi(2) + losses * ratio.powi(2))
+                / total
+                / games;
+        let s0 = score_from_elo(self.elo0);
+        let s1 = score_from_elo(self.elo1);
+        (s1 - s0) * (2.0 * ratio - s0 - s1) / (2.0 * variance)
+    }
+
+    pub fn state(&self, score: &Score) -> SprtState {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtState::AcceptH1
+        } else if llr <= lower {
+            SprtState::AcceptH0
+        } else {
+            SprtState::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn elo_and_score_are_inverse() {
+        assert_eq!(score_from_elo(0.0), 0.5);
+        assert!(close(elo_from_score(0.75), 190.85, 0.01));
+        for elo in [-400.0, -35.0, 0.0, 120.0, 800.0] {
+            assert!(close(elo_from_score(score_from_elo(elo)), elo, 1e-9));
+        }
+    }
+
+    #[test]
+    fn score_counts_points() {
+        let score = Score {
+            wins: 6,
+            draws: 2,
+            losses: 2,
+        };
+        assert_eq!(score.games(), 10);
+        assert_eq!(score.points(), 7.0);
+        assert_eq!(score.ratio(), Some(0.7));
+        assert_eq!(score.reversed().ratio(), Some(0.3));
+        assert_eq!(score.to_string(), "+6 =2 -2");
+        assert_eq!(Score::default().ratio(), None);
+    }
+
+    #[test]
+    fn elo_estimate_has_a_symmetric_interval() {
+        let score = Score {
+            wins: 30,
+            draws: 40,
+            losses: 30,
+        };
+        let estimate = score.elo().unwrap();
+        assert!(close(estimate.difference, 0.0, 1e-9));
+        // Standard error sqrt(0.15 / 100) of the score at 50%.
+        assert!(close(estimate.margin, 53.3, 0.5), "{}", estimate.margin);
+
+        let stronger = Score {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+        };
+        let estimate = stronger.elo().unwrap();
+        assert!(close(estimate.difference, elo_from_score(0.7), 1e-9));
+        let reversed = stronger.reversed().elo().unwrap();
+        assert!(close(reversed.difference, -estimate.difference, 1e-9));
+    }
+
+    #[test]
+    fn elo_is_unknown_for_a_perfect_score() {
+        let perfect = Score {
+            wins: 5,
+            draws: 0,
+            losses: 0,
+        };
+        assert_eq!(perfect.elo(), None);
+        assert_eq!(perfect.reversed().elo(), None);
+    }
+
+    #[test]
+    fn likelihood_of_superiority() {
+        let score = Score {
+            wins: 6,
+            draws: 10,
+            losses: 2,
+        };
+        // erf(1) = 0.8427.
+        assert!(close(
+            score.likelihood_of_superiority().unwrap(),
+            0.9214,
+            1e-4
+        ));
+        let even = Score {
+            wins: 3,
+            draws: 0,
+            losses: 3,
+        };
+        assert!(close(even.likelihood_of_superiority().unwrap(), 0.5, 1e-9));
+        assert_eq!(Score::default().likelihood_of_superiority(), None);
+    }
+
+    #[test]
+    fn sprt_bounds() {
+        let (lower, upper) = Sprt::new(0.0, 5.0, 0.05, 0.05).bounds();
+        assert!(close(lower, -2.944, 1e-3));
+        assert!(close(upper, 2.944, 1e-3));
+    }
+
+    #[test]
+    fn sprt_decides_clear_results_and_waits_on_close_ones() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        assert_eq!(sprt.llr(&Score::default()), 0.0);
+        let winning = Score {
+            wins: 300,
+            draws: 200,
+            losses: 100,
+        };
+        assert_eq!(sprt.state(&winning), SprtState::AcceptH1);
+        assert_eq!(sprt.state(&winning.reversed()), SprtState::AcceptH0);
+        let close_match = Score {
+            wins: 11,
+            draws: 20,
+            losses: 10,
+        };
+        assert_eq!(sprt.state(&close_match), SprtState::Continue);
+    }
+
+    #[test]
+    fn sprt_llr_grows_with_the_evidence() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        let small = Score {
+            wins: 12,
+            draws: 10,
+            losses: 8,
+        };
+        let large = Score {
+            wins: 120,
+            draws: 100,
+            losses: 80,
+        };
+        assert!(sprt.llr(&small) > 0.0);
+        assert!(sprt.llr(&large) > sprt.llr(&small));
+    }
+}
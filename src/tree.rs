@@ -0,0 +1,400 @@
+use crate::engine::{chess_move::ChessMove, error::GameError, position::Position};
+use crate::game::Game;
+
+/// Index of a move in a `GameTree`. Ids stay valid for the life of the tree;
+/// a deleted move just becomes unreachable.
+pub type NodeId = usize;
+
+#[derive(Clone)]
+struct Node {
+    position: Position,
+    // Move leading here and the node it was played from; `None` at the root.
+    parent: Option<(NodeId, ChessMove)>,
+    // Continuations, the main one first.
+    children: Vec<NodeId>,
+    comment: String,
+    nags: Vec<u8>,
+}
+
+/// A game with its variations: every node is a position reached by a move
+/// from its parent, and the first child of each node continues the main
+/// line.
+#[derive(Clone)]
+pub struct GameTree {
+    nodes: Vec<Node>,
+}
+
+impl GameTree {
+    /// The starting position, whose comment precedes the first move.
+    pub const ROOT: NodeId = 0;
+
+    pub fn new(start: Position) -> Self {
+        Self {
+            nodes: vec![Node {
+                position: start,
+                parent: None,
+                children: Vec::new(),
+                comment: String::new(),
+                nags: Vec::new(),
+            }],
+        }
+    }
+
+    /// A tree holding the moves of `game` as its main line.
+    pub fn from_game(game: &Game) -> Self {
+        let mut tree = Self::new(*game.start_position());
+        let mut node = Self::ROOT;
+        for &mv in game.moves() {
+            node = tree.play(node, mv).expect("the moves of a game are legal");
+        }
+        tree
+    }
+
+    pub fn start_position(&self) -> &Position {
+        &self.nodes[Self::ROOT].position
+    }
+
+    pub fn position(&self, node: NodeId) -> &Position {
+        &self.nodes[node].position
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node].parent.map(|(parent, _)| parent)
+    }
+
+    /// Move leading to `node`; `None` for the root.
+    pub fn mv(&self, node: NodeId) -> Option<ChessMove> {
+        self.nodes[node].parent.map(|(_, mv)| mv)
+    }
+
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node].children
+    }
+
+    pub fn comment(&self, node: NodeId) -> &str {
+        &self.nodes[node].comment
+    }
+
+    pub fn set_comment(&mut self, node: NodeId, comment: impl Into<String>) {
+        self.nodes[node].comment = comment.into();
+    }
+
+    /// Numeric annotation glyphs of the move leading to `node`, such as 1
+    /// for `!`.
+    pub fn nags(&self, node: NodeId) -> &[u8] {
+        &self.nodes[node].nags
+    }
+
+    pub fn set_nags(&mut self, node: NodeId, nags: Vec<u8>) {
+        self.nodes[node].nags = nags;
+    }
+
+    /// Plays `mv` from `node`, returning the node it leads to. A move that
+    /// was already played there is reused; a new one becomes the last
+    /// variation, or the main line if there was no move yet.
+    pub fn play(&mut self, node: NodeId, mv: ChessMove) -> Result<NodeId, GameError> {
+        if let Some(&child) = self
+            .children(node)
+            .iter()
+            .find(|&&child| self.mv(child) == Some(mv))
+        {
+            return Ok(child);
+        }
+        let position = self.position(node);
+        if !position.is_legal(&mv) {
+            return Err(GameError::IllegalMove(mv.to_string()));
+        }
+        let child = self.nodes.len();
+        self.nodes.push(Node {
+            position: position.play(&mv),
+            parent: Some((node, mv)),
+            children: Vec::new(),
+            comment: String::new(),
+            nags: Vec::new(),
+        });
+        self.nodes[node].children.push(child);
+        Ok(child)
+    }
+
+    /// Nodes from the root to `node`, both included.
+    pub fn path(&self, node: NodeId) -> Vec<NodeId> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(parent) = self.parent(current) {
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// `node` followed by the main continuation from it to its end.
+    pub fn continuation(&self, node: NodeId) -> Vec<NodeId> {
+        let mut line = vec![node];
+        let mut current = node;
+        while let Some(&next) = self.children(current).first() {
+            line.push(next);
+            current = next;
+        }
+        line
+    }
+
+    /// The main line, starting with the root.
+    pub fn mainline(&self) -> Vec<NodeId> {
+        self.continuation(Self::ROOT)
+    }
+
+    pub fn is_mainline(&self, node: NodeId) -> bool {
+        self.path(node)
+            .windows(2)
+            .all(|pair| self.children(pair[0]).first() == Some(&pair[1]))
+    }
+
+    /// Number of moves from the start to `node`.
+    pub fn ply(&self, node: NodeId) -> usize {
+        self.path(node).len() - 1
+    }
+
+    /// The moves from the start to `node` as a game.
+    pub fn game_at(&self, node: NodeId) -> Game {
+        let mut game = Game::from_position(*self.start_position());
+        for step in self.path(node).into_iter().skip(1) {
+            let mv = self.mv(step).expect("only the root has no move");
+            game.play(mv).expect("tree moves are legal");
+        }
+        game
+    }
+
+    /// Every node still in the tree, each before its continuations.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        let mut nodes = Vec::new();
+        let mut pending = vec![Self::ROOT];
+        while let Some(node) = pending.pop() {
+            nodes.push(node);
+            pending.extend(self.children(node).iter().rev());
+        }
+        nodes
+    }
+
+    /// Moves the variation holding `node` one place up among its siblings,
+    /// making it the main continuation if it was the first alternative.
+    /// Does nothing on the main line.
+    pub fn promote(&mut self, node: NodeId) {
+        let path = self.path(node);
+        for pair in path.windows(2).rev() {
+            let siblings = &mut self.nodes[pair[0]].children;
+            let index = siblings
+                .iter()
+                .position(|&child| child == pair[1])
+                .expect("a child is listed by its parent");
+            if index > 0 {
+                siblings.swap(index, index - 1);
+                return;
+            }
+        }
+    }
+
+    /// Makes every move leading to `node` the main continuation.
+    pub fn make_mainline(&mut self, node: NodeId) {
+        for pair in self.path(node).windows(2) {
+            let siblings = &mut self.nodes[pair[0]].children;
+            let index = siblings
+                .iter()
+                .position(|&child| child == pair[1])
+                .expect("a child is listed by its parent");
+            let child = siblings.remove(index);
+            siblings.insert(0, child);
+        }
+    }
+
+    /// Removes the move leading to `node` and everything after it. The root
+    /// cannot be deleted.
+    pub fn delete(&mut self, node: NodeId) {
+        if let Some(parent) = self.parent(node) {
+            self.nodes[parent].children.retain(|&child| child != node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::chess_move::parse_move;
+
+    fn play(tree: &mut GameTree, node: NodeId, text: &str) -> NodeId {
+        tree.play(node, parse_move(text).unwrap()).unwrap()
+    }
+
+    /// 1. e4 (1. d4 d5 (1... Nf6)) (1. c4) 1... e5 (1... c5 2. Nf3)
+    struct Sample {
+        tree: GameTree,
+        e4: NodeId,
+        d4: NodeId,
+        c4: NodeId,
+        e5: NodeId,
+        c5: NodeId,
+        nf3: NodeId,
+        d5: NodeId,
+        nf6: NodeId,
+    }
+
+    fn sample() -> Sample {
+        let mut tree = GameTree::new(Position::default());
+        let e4 = play(&mut tree, GameTree::ROOT, "e2e4");
+        let e5 = play(&mut tree, e4, "e7e5");
+        let d4 = play(&mut tree, GameTree::ROOT, "d2d4");
+        let d5 = play(&mut tree, d4, "d7d5");
+        let c4 = play(&mut tree, GameTree::ROOT, "c2c4");
+        let c5 = play(&mut tree, e4, "c7c5");
+        let nf3 = play(&mut tree, c5, "g1f3");
+        let nf6 = play(&mut tree, d4, "g8f6");
+        Sample {
+            tree,
+            e4,
+            d4,
+            c4,
+            e5,
+            c5,
+            nf3,
+            d5,
+            nf6,
+        }
+    }
+
+    #[test]
+    fn playing_a_known_move_reuses_its_node() {
+        let mut sample = sample();
+        let tree = &mut sample.tree;
+        let count = tree.nodes().len();
+        assert_eq!(
+            tree.play(GameTree::ROOT, parse_move("e2e4").unwrap()),
+            Ok(sample.e4)
+        );
+        assert_eq!(
+            tree.play(sample.e4, parse_move("c7c5").unwrap()),
+            Ok(sample.c5)
+        );
+        assert_eq!(tree.nodes().len(), count);
+        assert_eq!(
+            tree.children(GameTree::ROOT),
+            [sample.e4, sample.d4, sample.c4]
+        );
+
+        assert_eq!(
+            tree.play(GameTree::ROOT, parse_move("e2e5").unwrap()),
+            Err(GameError::IllegalMove("e2e5".to_string()))
+        );
+        assert_eq!(tree.nodes().len(), count);
+    }
+
+    #[test]
+    fn paths_and_continuations() {
+        let Sample {
+            tree,
+            e4,
+            c5,
+            nf3,
+            d4,
+            d5,
+            e5,
+            ..
+        } = sample();
+        assert_eq!(tree.path(GameTree::ROOT), [GameTree::ROOT]);
+        assert_eq!(tree.path(nf3), [GameTree::ROOT, e4, c5, nf3]);
+        assert_eq!(tree.ply(nf3), 3);
+        assert_eq!(tree.continuation(d4), [d4, d5]);
+        assert_eq!(tree.continuation(c5), [c5, nf3]);
+        assert_eq!(tree.mainline(), [GameTree::ROOT, e4, e5]);
+        assert!(tree.is_mainline(e5));
+        assert!(!tree.is_mainline(nf3));
+
+        let moves: Vec<String> = tree
+            .game_at(nf3)
+            .moves()
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect();
+        assert_eq!(moves, ["e2e4", "c7c5", "g1f3"]);
+        assert_eq!(
+            tree.position(nf3).to_fen(),
+            tree.game_at(nf3).position().to_fen()
+        );
+    }
+
+    #[test]
+    fn promote_moves_a_variation_up_one_place() {
+        let Sample {
+            mut tree,
+            e4,
+            d4,
+            c4,
+            e5,
+            c5,
+            nf3,
+            ..
+        } = sample();
+        tree.promote(c4);
+        assert_eq!(tree.children(GameTree::ROOT), [e4, c4, d4]);
+        tree.promote(c4);
+        assert_eq!(tree.children(GameTree::ROOT), [c4, e4, d4]);
+        // Already the main continuation.
+        tree.promote(c4);
+        assert_eq!(tree.children(GameTree::ROOT), [c4, e4, d4]);
+
+        // The nearest branch point decides which siblings trade places.
+        tree.promote(nf3);
+        assert_eq!(tree.children(e4), [c5, e5]);
+        assert_eq!(tree.children(GameTree::ROOT), [c4, e4, d4]);
+        tree.promote(nf3);
+        assert_eq!(tree.children(GameTree::ROOT), [e4, c4, d4]);
+        assert_eq!(tree.mainline(), [GameTree::ROOT, e4, c5, nf3]);
+    }
+
+    #[test]
+    fn make_mainline_puts_every_move_first() {
+        let Sample {
+            mut tree,
+            e4,
+            d4,
+            c4,
+            d5,
+            nf6,
+            ..
+        } = sample();
+        tree.make_mainline(nf6);
+        assert_eq!(tree.mainline(), [GameTree::ROOT, d4, nf6]);
+        // The others keep their order behind it.
+        assert_eq!(tree.children(GameTree::ROOT), [d4, e4, c4]);
+        assert_eq!(tree.children(d4), [nf6, d5]);
+
+        tree.make_mainline(c4);
+        assert_eq!(tree.children(GameTree::ROOT), [c4, d4, e4]);
+        assert!(tree.is_mainline(c4));
+        assert!(!tree.is_mainline(nf6));
+    }
+
+    #[test]
+    fn delete_removes_the_subtree_only() {
+        let Sample {
+            mut tree,
+            e4,
+            d4,
+            c4,
+            e5,
+            c5,
+            nf3,
+            ..
+        } = sample();
+        tree.delete(c5);
+        assert_eq!(tree.children(e4), [e5]);
+        assert!(!tree.nodes().contains(&c5));
+        assert!(!tree.nodes().contains(&nf3));
+
+        tree.delete(d4);
+        assert_eq!(tree.children(GameTree::ROOT), [e4, c4]);
+        assert_eq!(tree.nodes(), [GameTree::ROOT, e4, e5, c4]);
+
+        tree.delete(GameTree::ROOT);
+        assert_eq!(tree.nodes(), [GameTree::ROOT, e4, e5, c4]);
+    }
+}
@@ -0,0 +1,102 @@
+use chess::engine::{chess_move::parse_move, position::Position};
+use std::io::{self, BufRead};
+use std::process;
+
+/// How the stand-in answers `go`, picked by its first argument.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Script {
+    /// Reports a small advantage and plays the first legal move at once.
+    Play,
+    /// Same, announcing a mate in two.
+    Mate,
+    /// Same, announcing that it gets mated next move.
+    Mated,
+    /// Keeps thinking until told to stop.
+    Wait,
+    /// Ignores `go` and `stop` altogether.
+    Hang,
+    /// Exits as soon as it is asked for a move.
+    Crash,
+    /// Answers with a move that is not legal.
+    Illegal,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let script = match args.next().as_deref() {
+        Some("play") | None => Script::Play,
+        Some("mate") => Script::Mate,
+        Some("mated") => Script::Mated,
+        Some("wait") => Script::Wait,
+        Some("hang") => Script::Hang,
+        Some("crash") => Script::Crash,
+        Some("illegal") => Script::Illegal,
+        Some(other) => {
+            eprintln!("unknown script '{}'", other);
+            process::exit(2);
+        }
+    };
+
+    let mut position = Position::default();
+    let mut searching = false;
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("uci") => {
+                println!("id name Scripted Engine");
+                println!("id author nobody");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("position") => position = parse_position(&tokens[1..]),
+            Some("go") => match script {
+                Script::Hang => {}
+                Script::Crash => process::exit(1),
+                Script::Wait => searching = true,
+                _ => answer(script, &position),
+            },
+            Some("stop") if searching => {
+                searching = false;
+                answer(script, &position);
+            }
+            Some("quit") => break,
+            _ => {}
+        }
+    }
+}
+
+fn parse_position(args: &[&str]) -> Position {
+    let moves_at = args.iter().position(|&token| token == "moves");
+    let setup = &args[..moves_at.unwrap_or(args.len())];
+    let mut position = match setup.first() {
+        Some(&"fen") => Position::from_fen(&setup[1..].join(" ")).expect("valid FEN"),
+        _ => Position::default(),
+    };
+    for text in moves_at.map_or(&[][..], |index| &args[index + 1..]) {
+        position = position.play(&parse_move(text).expect("valid move"));
+    }
+    position
+}
+
+fn answer(script: Script, position: &Position) {
+    let Some(best) = position.legal_moves().first().copied() else {
+        println!("bestmove 0000");
+        return;
+    };
+    let score = match script {
+        Script::Mate => "mate 2",
+        Script::Mated => "mate -1",
+        _ => "cp 12",
+    };
+    println!("info string thinking");
+    println!(
+        "info depth 3 seldepth 5 score {} nodes 4321 pv {}",
+        score, best
+    );
+    if script == Script::Illegal {
+        println!("bestmove a1a1");
+    } else {
+        println!("bestmove {}", best);
+    }
+}
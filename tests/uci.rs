@@ -0,0 +1,117 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `uci` binary driven through its standard input and output.
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_uci"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("the uci binary starts");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("uci");
+        engine.read_until("uciok");
+        engine
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Lines up to and including the first one starting with `prefix`.
+    fn read_until(&mut self, prefix: &str) -> Vec<String> {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut seen = Vec::new();
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let line = self
+                .lines
+                .recv_timeout(wait)
+                .unwrap_or_else(|_| panic!("no '{}' in time after {:?}", prefix, seen));
+            let done = line.starts_with(prefix);
+            seen.push(line);
+            if done {
+                return seen;
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn stop_ends_an_infinite_search() {
+    for _ in 0..20 {
+        let mut engine = Engine::start();
+        engine.send("position startpos");
+        engine.send("go infinite");
+        engine.send("stop");
+        engine.send("isready");
+        let lines = engine.read_until("readyok");
+        assert!(
+            lines.iter().any(|line| line.starts_with("bestmove ")),
+            "{:?}",
+            lines
+        );
+    }
+}
+
+#[test]
+fn weakened_engine_reports_only_the_requested_lines() {
+    let mut engine = Engine::start();
+    engine.send("setoption name Skill Level value 5");
+    engine.send("position startpos");
+    engine.send("go depth 3");
+    let lines = engine.read_until("bestmove");
+    assert!(lines.iter().any(|line| line.contains(" multipv 1 ")));
+    assert!(!lines.iter().any(|line| line.contains(" multipv 2 ")));
+}
+
+#[test]
+fn illegal_fen_is_rejected() {
+    let mut engine = Engine::start();
+    engine.send("position fen 4k3/8/8/8/8/8/8/4K2K w - - 0 1");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("info string illegal position")),
+        "{:?}",
+        lines
+    );
+}
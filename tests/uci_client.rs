@@ -0,0 +1,138 @@
+use chess::engine::{
+    chess_move::parse_move,
+    error::PlayerError,
+    player::{MoveRequest, Player},
+    search::{MATE_SCORE, SearchLimits, SearchResult},
+    uci_client::UciClient,
+};
+use chess::game::Game;
+use std::env;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The stand-in engine, built by `cargo test` as an example next to the
+/// `deps` directory this test runs from.
+fn scripted_engine() -> PathBuf {
+    let exe = env::current_exe().expect("the test binary has a path");
+    let path = exe
+        .parent()
+        .and_then(|deps| deps.parent())
+        .expect("the test binary lives in a target directory")
+        .join("examples")
+        .join(format!("scripted_engine{}", env::consts::EXE_SUFFIX));
+    assert!(
+        path.exists(),
+        "{} is missing; build it with `cargo build --example scripted_engine`",
+        path.display()
+    );
+    path
+}
+
+fn engine(script: &str) -> UciClient {
+    UciClient::spawn(scripted_engine(), &[script.to_string()]).expect("the scripted engine starts")
+}
+
+fn depth_request() -> MoveRequest {
+    MoveRequest {
+        limits: SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        },
+        clock: None,
+    }
+}
+
+#[test]
+fn handshake_reads_the_engine_name() {
+    let mut client = engine("play");
+    assert_eq!(client.name(), "Scripted Engine");
+    assert!(client.is_alive());
+    client.new_game().unwrap();
+    client.set_option("Hash", "16").unwrap();
+}
+
+#[test]
+fn best_move_and_info_are_read() {
+    let mut client = engine("play");
+    let mut game = Game::new();
+    game.play(parse_move("e2e4").unwrap()).unwrap();
+    let mv = client.choose_move(&game, &depth_request()).unwrap();
+    assert!(game.position().is_legal(&mv));
+
+    let info = client.last_info().unwrap();
+    assert_eq!(info.depth, 3);
+    assert_eq!(info.nodes, 4321);
+    assert_eq!(info.pv, [mv]);
+    assert_eq!(info.score, Some(12));
+}
+
+#[test]
+fn mate_scores_use_the_search_scale() {
+    let mut client = engine("mate");
+    client.choose_move(&Game::new(), &depth_request()).unwrap();
+    let score = client.last_info().unwrap().score.unwrap();
+    assert_eq!(score, MATE_SCORE - 3);
+    assert_eq!(SearchResult::mate_in(score), Some(2));
+
+    let mut client = engine("mated");
+    client.choose_move(&Game::new(), &depth_request()).unwrap();
+    let score = client.last_info().unwrap().score.unwrap();
+    assert_eq!(SearchResult::mate_in(score), Some(-1));
+}
+
+#[test]
+fn engine_is_stopped_when_its_time_is_up() {
+    let mut client = engine("wait");
+    client.set_move_timeout(Duration::from_millis(200));
+    let mv = client.choose_move(&Game::new(), &depth_request()).unwrap();
+    assert!(Game::new().position().is_legal(&mv));
+    assert!(client.is_alive());
+}
+
+#[test]
+fn stop_signal_is_passed_on_to_the_engine() {
+    let mut client = engine("wait");
+    let request = depth_request();
+    let stop = request.limits.stop.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        stop.stop();
+    });
+    let started = Instant::now();
+    client.choose_move(&Game::new(), &request).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn unresponsive_engine_is_killed() {
+    let mut client = engine("hang");
+    client.set_move_timeout(Duration::from_millis(100));
+    let result = client.choose_move(&Game::new(), &depth_request());
+    assert!(
+        matches!(result, Err(PlayerError::Timeout(_))),
+        "{:?}",
+        result
+    );
+    assert!(!client.is_alive());
+}
+
+#[test]
+fn crash_is_reported() {
+    let mut client = engine("crash");
+    let result = client.choose_move(&Game::new(), &depth_request());
+    assert_eq!(result, Err(PlayerError::Crashed));
+    let result = client.choose_move(&Game::new(), &depth_request());
+    assert_eq!(result, Err(PlayerError::Crashed));
+}
+
+#[test]
+fn illegal_best_move_is_rejected() {
+    let mut client = engine("illegal");
+    let result = client.choose_move(&Game::new(), &depth_request());
+    assert!(
+        matches!(result, Err(PlayerError::IllegalMove(_))),
+        "{:?}",
+        result
+    );
+}
@@ -0,0 +1,130 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `xboard` binary driven through its standard input and output.
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_xboard"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("the xboard binary starts");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("xboard");
+        engine.send("protover 2");
+        engine.read_until("feature done=1");
+        engine
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Lines up to and including the first one starting with `prefix`.
+    fn read_until(&mut self, prefix: &str) -> Vec<String> {
+        let deadline = Instant::now() + TIMEOUT;
+        let mut seen = Vec::new();
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let line = self
+                .lines
+                .recv_timeout(wait)
+                .unwrap_or_else(|_| panic!("no '{}' in time after {:?}", prefix, seen));
+            let done = line.starts_with(prefix);
+            seen.push(line);
+            if done {
+                return seen;
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn move_now_ends_a_long_search() {
+    for _ in 0..20 {
+        let mut engine = Engine::start();
+        engine.send("new");
+        engine.send("st 60");
+        engine.send("usermove e2e4");
+        engine.send("?");
+        engine.read_until("move ");
+    }
+}
+
+#[test]
+fn force_cancels_a_long_search() {
+    for _ in 0..20 {
+        let mut engine = Engine::start();
+        engine.send("new");
+        engine.send("st 60");
+        engine.send("usermove e2e4");
+        engine.send("force");
+        engine.send("ping 1");
+        let lines = engine.read_until("pong 1");
+        assert!(
+            !lines.iter().any(|line| line.starts_with("move ")),
+            "{:?}",
+            lines
+        );
+    }
+}
+
+#[test]
+fn illegal_setboard_is_rejected() {
+    let mut engine = Engine::start();
+    engine.send("force");
+    engine.send("setboard 4k3/8/8/8/8/8/8/4K2K w - - 0 1");
+    engine.send("ping 1");
+    let lines = engine.read_until("pong 1");
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.starts_with("tellusererror Illegal position")),
+        "{:?}",
+        lines
+    );
+
+    engine.send("setboard 4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    engine.send("ping 2");
+    let lines = engine.read_until("pong 2");
+    assert!(
+        !lines.iter().any(|line| line.starts_with("tellusererror")),
+        "{:?}",
+        lines
+    );
+}